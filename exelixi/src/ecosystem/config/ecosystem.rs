@@ -3,12 +3,15 @@ use std::{
     path::PathBuf,
 };
 
+use bevy::reflect::ReflectRef;
 use bevy_egui::egui::Color32;
 
 use crate::ecosystem::*;
 
 use super::{environment::EnvironmentConfig, species::SpeciesConfig, *};
 
+pub const DEFAULT_STATISTICS_MAX_LENGTH: u32 = 1500;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct EcosystemStatsConfig {
     // Defines the number of steps before data aggregation starts..
@@ -17,6 +20,11 @@ pub struct EcosystemStatsConfig {
     // Defines the number of steps between each data aggregation.
     // If not provided, will be 0.1*smallest generation length.
     pub aggregation_rate: Option<u32>,
+    // Maximum number of aggregated statistics kept per species.
+    // When reached, the most recent third is kept and the older ones are merged two by two.
+    // If not provided, will be 1500.
+    #[serde(default)]
+    pub max_length: Option<u32>,
 }
 #[derive(Serialize, Deserialize)]
 pub struct UserEcosystemConfig {
@@ -36,6 +44,7 @@ pub struct EcosystemConfig {
     pub environment: EnvironmentConfig,
     pub statistics_aggregation_rate: u32,
     pub statistics_aggregation_start: u32,
+    pub statistics_max_length: u32,
     pub species: BTreeMap<SpeciesId, SpeciesConfig>,
 }
 impl EcosystemConfig {
@@ -67,9 +76,21 @@ impl EcosystemConfig {
                 }
             }
         };
-        Self::from_user_config(user_config)
+        match Self::from_user_config(user_config) {
+            Ok(config) => config,
+            Err(report) => panic!("Invalid configuration:\n{report}"),
+        }
     }
-    pub fn from_user_config(user_config: UserEcosystemConfig) -> Self {
+    // Config ready to run, or the report of its errors.
+    // Warnings are logged.
+    pub fn from_user_config(user_config: UserEcosystemConfig) -> Result<Self, ConfigReport> {
+        let report = user_config.validate();
+        if report.has_errors() {
+            return Err(report);
+        }
+        for warning in report.warnings() {
+            log::warn!("Configuration {warning}");
+        }
         let mut species = BTreeMap::new();
         let mut species_name_to_id = HashMap::new();
         for (i, mut species_config) in user_config.species.into_iter().enumerate() {
@@ -88,15 +109,6 @@ impl EcosystemConfig {
             {
                 min_generation_length = min_generation_length.min(generation_length);
             }
-            // Check that reproduction by Birth has an Uterus
-            if matches!(species.reproduction, ReproductionConfig::Birth { .. })
-                && species.uterus.is_none()
-            {
-                panic!(
-                    "Invalid configuration: Species {} has a Birth reproduction but no Uterus",
-                    species.name
-                );
-            }
         }
         let statistics_aggregation_rate =
             user_config.statistics.aggregation_rate.unwrap_or_else(|| {
@@ -107,12 +119,17 @@ impl EcosystemConfig {
                 }
             });
         let statistics_aggregation_start = user_config.statistics.aggregation_start.unwrap_or(0);
-        Self {
+        let statistics_max_length = user_config
+            .statistics
+            .max_length
+            .unwrap_or(DEFAULT_STATISTICS_MAX_LENGTH);
+        Ok(Self {
             environment: user_config.environment,
             species,
             statistics_aggregation_rate,
             statistics_aggregation_start,
-        }
+            statistics_max_length,
+        })
     }
     pub fn with_statistics_aggregation_override(
         mut self,
@@ -144,4 +161,115 @@ impl EcosystemConfig {
     pub fn get_species_name(&self, species_id: &SpeciesId) -> &str {
         &self.species[species_id].name
     }
+    // Changes from this config to the new one, when they can be applied to a running ecosystem.
+    // Living organisms must keep their components and chromosomes valid, so the species, their organs,
+    // their reproduction kind, their brain and their chromosome layout cannot change.
+    pub fn hot_reload_changes(&self, new: &EcosystemConfig) -> Result<Vec<String>, String> {
+        let names = |config: &EcosystemConfig| {
+            config
+                .species
+                .values()
+                .map(|species| species.name.clone())
+                .collect::<Vec<_>>()
+        };
+        if names(self) != names(new) {
+            return Err(format!(
+                "species cannot be added, removed or reordered: {:?} -> {:?}",
+                names(self),
+                names(new)
+            ));
+        }
+        let mut changes = vec![];
+        reflect_changes(
+            "environment",
+            &self.environment,
+            &new.environment,
+            &mut changes,
+        );
+        for (old, new) in self.species.values().zip(new.species.values()) {
+            let organs = |species: &SpeciesConfig| {
+                [
+                    species.eye.is_some(),
+                    species.leaf.is_some(),
+                    species.locomotion.is_some(),
+                    species.mouth.is_some(),
+                    species.uterus.is_some(),
+                ]
+            };
+            if organs(old) != organs(new) {
+                return Err(format!("{}: organs cannot be added or removed", old.name));
+            }
+            if std::mem::discriminant(&old.reproduction)
+                != std::mem::discriminant(&new.reproduction)
+            {
+                return Err(format!("{}: reproduction kind cannot change", old.name));
+            }
+            let same_layout = old.chromosome_layout.len() == new.chromosome_layout.len()
+                && old
+                    .chromosome_layout
+                    .iter()
+                    .zip(new.chromosome_layout.iter())
+                    .all(|(old, new)| {
+                        old.name == new.name
+                            && old.kind == new.kind
+                            && old.min == new.min
+                            && old.max == new.max
+                    });
+            if !same_layout {
+                return Err(format!(
+                    "{}: chromosome layout changes (eye genes, brain topology) cannot be applied to living organisms",
+                    old.name
+                ));
+            }
+            let mut brain_changes = vec![];
+            reflect_changes("brain", &old.brain, &new.brain, &mut brain_changes);
+            if !brain_changes.is_empty() {
+                return Err(format!(
+                    "{}: brain cannot change: {}",
+                    old.name,
+                    brain_changes.join(", ")
+                ));
+            }
+            reflect_changes(&format!("species.{}", old.name), old, new, &mut changes);
+        }
+        Ok(changes)
+    }
+}
+
+// Fields computed from the rest of the config, their changes are not reported.
+const DERIVED_CONFIG_FIELDS: [&str; 2] = ["id", "chromosome_layout"];
+
+// Appends a 'path: old -> new' line for each value that differs between the two configs.
+fn reflect_changes(path: &str, old: &dyn Reflect, new: &dyn Reflect, changes: &mut Vec<String>) {
+    match (old.reflect_ref(), new.reflect_ref()) {
+        (ReflectRef::Struct(old), ReflectRef::Struct(new)) => {
+            for (i, old_field) in old.iter_fields().enumerate() {
+                let name = old.name_at(i).unwrap_or_default();
+                if DERIVED_CONFIG_FIELDS.contains(&name) {
+                    continue;
+                }
+                if let Some(new_field) = new.field(name) {
+                    reflect_changes(&format!("{path}.{name}"), old_field, new_field, changes);
+                }
+            }
+        }
+        (ReflectRef::Enum(old), ReflectRef::Enum(new))
+            if old.variant_name() == new.variant_name() =>
+        {
+            for (i, old_field) in old.iter_fields().enumerate() {
+                if let Some(new_field) = new.field_at(i) {
+                    let path = match old_field.name() {
+                        Some(name) => format!("{path}.{name}"),
+                        None => path.to_string(),
+                    };
+                    reflect_changes(&path, old_field.value(), new_field, changes);
+                }
+            }
+        }
+        _ => {
+            if old.reflect_partial_eq(new) != Some(true) {
+                changes.push(format!("{path}: {old:?} -> {new:?}"));
+            }
+        }
+    }
 }
@@ -3,6 +3,7 @@ mod environment;
 mod organs;
 mod reproduction;
 mod species;
+mod validation;
 mod visualization;
 
 pub use crate::ecosystem::*;
@@ -10,6 +11,7 @@ pub use ecosystem::*;
 pub use organs::*;
 pub use reproduction::*;
 pub use species::*;
+pub use validation::*;
 pub use visualization::*;
 
 #[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
@@ -18,3 +20,12 @@ pub enum ConfigValue<T> {
     Gene { min: T, max: T },
     Neuron { min: T, max: T },
 }
+impl<T: Copy> ConfigValue<T> {
+    // Smallest and largest values.
+    pub fn bounds(&self) -> (T, T) {
+        match *self {
+            ConfigValue::Fixed(v) => (v, v),
+            ConfigValue::Gene { min, max } | ConfigValue::Neuron { min, max } => (min, max),
+        }
+    }
+}
@@ -71,19 +71,129 @@ pub struct EyeConfig {
     pub visible_species: Vec<SpeciesId>,
 }
 impl EyeConfig {
+    // Maximum number of eye cells an eye with this config can have.
+    pub fn n_cells_max(&self) -> usize {
+        self.n_cells.bounds().1 as usize
+    }
+    // Number of sensors an eye with this config provides to the brain.
+    pub fn n_sensors(&self) -> usize {
+        self.n_cells_max() * self.cell_sensors.n_sensors(self.visible_species.len())
+    }
+    // Number of genes an eye with this config uses in the chromosome.
+    pub fn n_genes(&self) -> usize {
+        [
+            matches!(self.fov_angle, ConfigValue::Gene { .. }),
+            matches!(self.fov_range, ConfigValue::Gene { .. }),
+            matches!(self.n_cells, ConfigValue::Gene { .. }),
+        ]
+        .into_iter()
+        .filter(|is_gene| *is_gene)
+        .count()
+    }
+    // Description of the eye genes, in chromosome order.
+    pub fn layout(&self) -> Vec<ga::Locus> {
+        let mut loci = vec![];
+        if let ConfigValue::Gene { min, max } = self.fov_angle {
+            loci.push(ga::Locus::continuous("eye.fov_angle", min, max));
+        }
+        if let ConfigValue::Gene { min, max } = self.fov_range {
+            loci.push(ga::Locus::continuous("eye.fov_range", min, max));
+        }
+        if let ConfigValue::Gene { min, max } = self.n_cells {
+            loci.push(ga::Locus::integer("eye.n_cells", min as f32, max as f32));
+        }
+        loci
+    }
     pub fn update(&mut self, species_name_to_id: &HashMap<String, SpeciesId>) {
         self.visible_species = self
             .visible
             .iter()
-            .map(|name| {
-                *species_name_to_id
-                    .get(name)
-                    .expect("Configuration of an Eye.visible contains an invalid species.")
-            })
+            .filter_map(|name| species_name_to_id.get(name).copied())
             .collect();
     }
 }
 
+// Size of a brain hidden layer.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum LayerSize {
+    // Multiple of the number of sensors of the organism, rounded up.
+    Sensors(f32),
+    // Multiple of the number of actuators of the organism, rounded up.
+    Actuators(f32),
+    // Absolute number of neurons.
+    Neurons(usize),
+}
+impl LayerSize {
+    pub fn n_neurons(&self, n_sensors: usize, n_actuators: usize) -> usize {
+        match *self {
+            LayerSize::Sensors(factor) => (factor * n_sensors as f32).ceil() as usize,
+            LayerSize::Actuators(factor) => (factor * n_actuators as f32).ceil() as usize,
+            LayerSize::Neurons(n) => n,
+        }
+        .max(1)
+    }
+}
+
+// Kind of neural network used by the brain.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Default)]
+pub enum BrainKind {
+    // Fixed topology of fully connected layers, defined by hidden_layers.
+    #[default]
+    Layered,
+    // NEAT network, starting with all sensors connected to all actuators,
+    // whose topology evolves through mutations adding nodes and connections.
+    Neat {
+        // Probability, per mutation, to split a connection with a new node.
+        add_node_chance: f32,
+        // Probability, per mutation, to add a new connection between two nodes.
+        add_connection_chance: f32,
+    },
+}
+
+// Configuration for the brain.
+// The brain exists for all organisms with an eye, its input layer is made of all sensors,
+// and its output layer of all actuators.
+// Missing fields take their default value.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BrainConfig {
+    pub kind: BrainKind,
+    // Sizes of the hidden layers, from the input side to the output side.
+    // Unused for Neat brains, defaults to a single hidden layer of 2*n_sensors neurons.
+    // ex: [Sensors(2), Sensors(3), Actuators(2)] is 3 hidden layers of
+    // 2*n_sensors, 3*n_sensors and 2*n_actuators neurons.
+    pub hidden_layers: Vec<LayerSize>,
+    // Activation function of the hidden layers neurons.
+    pub hidden_activation: nn::Activation,
+    // Activation function of the output layer neurons, feeding the actuators.
+    pub output_activation: nn::Activation,
+    // Indices of the hidden layers, starting at 0, that are recurrent.
+    // A recurrent layer receives its outputs of the previous step as extra inputs,
+    // giving a memory to the organism.
+    pub recurrent_layers: Vec<usize>,
+    // Energy cost, per step, of each enabled connection between neurons.
+    pub connection_cost: f32,
+    // Probability for each connection to be switched on or off when mutating.
+    // When 0.0 connections are never disabled.
+    pub connection_toggle_chance: f32,
+    // Maximum absolute value of the weights and biases, unbounded when None.
+    pub max_weight: Option<f32>,
+}
+impl Default for BrainConfig {
+    fn default() -> Self {
+        Self {
+            kind: BrainKind::default(),
+            hidden_layers: vec![LayerSize::Sensors(2.0)],
+            hidden_activation: nn::Activation::default(),
+            output_activation: nn::Activation::default(),
+            recurrent_layers: vec![],
+            connection_cost: 0.0,
+            connection_toggle_chance: 0.0,
+            max_weight: None,
+        }
+    }
+}
+
 // Configuration for the body
 #[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
 pub struct BodyConfig {
@@ -122,7 +232,7 @@ impl MouthConfig {
         self.edible_species = self
             .edible
             .iter()
-            .map(|name| species_name_to_id[name])
+            .filter_map(|name| species_name_to_id.get(name).copied())
             .collect();
     }
 }
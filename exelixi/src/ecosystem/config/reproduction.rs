@@ -15,10 +15,27 @@ pub enum ReproductionConfig {
         fertility_rate: f32,
         mutation_chance: f32,
         mutation_amplitude: f32,
+        #[serde(default)]
+        crossover: CrossoverConfig,
+        #[serde(default)]
+        mutation: MutationConfig,
         // Maximum distance child are spawning from survivors position
         // When None, child spawn randomly in the environment.
         #[serde(default)]
         child_spawn_distance: Option<f32>,
+        // How survivors are chosen as parents of the next generation.
+        #[serde(default)]
+        selection: SelectionConfig,
+        // Number of fittest survivors copied unchanged into the next generation.
+        #[serde(default)]
+        elitism: usize,
+        // Keeps several niches of different strategies inside the species.
+        #[serde(default)]
+        niching: Option<NichingConfig>,
+        // How survivors are evaluated at the end of a generation.
+        // Defaults to their body energy.
+        #[serde(default)]
+        fitness: FitnessConfig,
     },
     Birth {
         // Minimum age for an organism to give birth.
@@ -30,5 +47,140 @@ pub enum ReproductionConfig {
         child_energy_pct: f32,
         mutation_chance: f32,
         mutation_amplitude: f32,
+        #[serde(default)]
+        crossover: CrossoverConfig,
+        #[serde(default)]
+        mutation: MutationConfig,
+    },
+}
+impl ReproductionConfig {
+    // Initial value of the step size gene, for species with self-adaptive mutation.
+    pub fn self_adaptive_step(&self) -> Option<f32> {
+        match self {
+            ReproductionConfig::GenerationEvolution {
+                mutation: MutationConfig::SelfAdaptive { .. },
+                mutation_amplitude,
+                ..
+            }
+            | ReproductionConfig::Birth {
+                mutation: MutationConfig::SelfAdaptive { .. },
+                mutation_amplitude,
+                ..
+            } => Some(*mutation_amplitude),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Default)]
+pub enum SelectionConfig {
+    // Probability to be selected is proportional to fitness
+    #[default]
+    RouletteWheel,
+    // The fittest among `size` randomly picked organisms is selected
+    Tournament {
+        size: usize,
+    },
+    // Probability to be selected is proportional to the fitness rank
+    Rank,
+    // Selection is uniform among the fittest `ratio` of the organisms
+    Truncation {
+        ratio: f32,
+    },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Default)]
+pub enum CrossoverConfig {
+    // Each gene comes from either parent
+    #[default]
+    Uniform,
+    // Genes before a random point come from one parent, the others from the other parent
+    SinglePoint,
+    // Genes between two random points come from one parent, the others from the other parent
+    TwoPoint,
+    // BLX-alpha, each gene is random between the parent genes, extended by alpha on each side
+    ArithmeticBlend {
+        alpha: f32,
     },
+    // Genes of each organ come together from either parent
+    Segment,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Default)]
+pub enum MutationConfig {
+    // Mutated genes change by a uniform random value up to mutation_amplitude
+    #[default]
+    Gaussian,
+    // Mutated genes change by a normal random value of standard deviation mutation_amplitude
+    Normal,
+    // Mutated genes are reset to a random value within their bounds
+    UniformReset,
+    // Mutated genes change by a normal random value whose standard deviation
+    // is an extra gene, itself mutated. mutation_amplitude is its initial value.
+    SelfAdaptive {
+        learning_rate: f32,
+    },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub enum NichingConfig {
+    // The fitness of each organism is shared with the organisms whose chromosome is closer than radius.
+    FitnessSharing { radius: f32, alpha: f32 },
+    // Organisms are clustered into sub-species of chromosomes closer than threshold,
+    // each sub-species having a number of children proportional to its mean fitness.
+    Clustering { threshold: f32 },
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct FitnessConfig {
+    // Objectives to maximize.
+    // With several objectives, the fitness is the NSGA-II Pareto rank and crowding,
+    // best used with a Tournament selection.
+    pub objectives: Vec<FitnessObjective>,
+    // Novelty search settings, used by the Novelty objective.
+    #[serde(default)]
+    pub novelty: NoveltyConfig,
+}
+impl Default for FitnessConfig {
+    fn default() -> Self {
+        Self {
+            objectives: vec![FitnessObjective::Energy],
+            novelty: NoveltyConfig::default(),
+        }
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FitnessObjective {
+    // Body energy left at the end of the generation
+    Energy,
+    // Number of steps survived
+    Age,
+    // Total energy eaten through the Mouth
+    EnergyEaten,
+    // Total distance travelled through Locomotion
+    Distance,
+    // Number of children. Organisms of a generation have none before it ends,
+    // they get the mean number of surviving children of their parents, plus the children
+    // they gave birth to, if any.
+    Offspring,
+    // Distance of the organism final position to the final positions of the others
+    // and of the archived past organisms.
+    Novelty,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct NoveltyConfig {
+    // Number of nearest neighbours the novelty is measured against.
+    pub k_nearest: usize,
+    // Probability of each organism final position to be archived.
+    pub archive_chance: f32,
+}
+impl Default for NoveltyConfig {
+    fn default() -> Self {
+        Self {
+            k_nearest: 15,
+            archive_chance: 0.02,
+        }
+    }
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use bevy::reflect::Reflect;
 
-use super::organs::{BodyConfig, EyeConfig, LocomotionConfig};
+use super::organs::{BodyConfig, BrainConfig, EyeConfig, LocomotionConfig};
 use super::reproduction::ReproductionConfig;
 use super::*;
 
@@ -16,6 +16,10 @@ pub struct SpeciesConfig {
     #[serde(skip)]
     pub id: SpeciesId,
     pub body: BodyConfig,
+    // Brain topology, used only when the organism has a brain.
+    // Defaults to a single hidden layer of 2*n_sensors neurons.
+    #[serde(default)]
+    pub brain: BrainConfig,
     // Optional eye vision organ
     pub eye: Option<EyeConfig>,
     // Optional eye vision organ
@@ -29,6 +33,12 @@ pub struct SpeciesConfig {
     // Mean of creating new organism
     pub reproduction: ReproductionConfig,
     pub visualization: OrganismVisualizationConfig,
+    // Chromosomes the new random organisms are sampled from, instead of being fully random.
+    #[serde(default)]
+    pub gene_pool: Option<GenePoolConfig>,
+    // Description of the genes of the organisms chromosome, computed from this config.
+    #[serde(skip)]
+    pub chromosome_layout: ga::ChromosomeLayout,
 }
 impl SpeciesConfig {
     pub fn update(&mut self, species_name_to_id: &HashMap<String, SpeciesId>) {
@@ -38,14 +48,26 @@ impl SpeciesConfig {
         if let Some(mouth_config) = self.mouth.as_mut() {
             mouth_config.update(species_name_to_id);
         }
+        self.chromosome_layout = OrganismIndividual::chromosome_layout(self);
     }
 }
 
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone)]
+pub struct GenePoolConfig {
+    // Path of a gene pool file, exported from a previous simulation.
+    pub path: String,
+    // Mutation of the sampled chromosomes, none by default.
+    #[serde(default)]
+    pub mutation_chance: f32,
+    #[serde(default)]
+    pub mutation_amplitude: f32,
+}
+
 // Identifier for each different species in the simulation
 #[derive(
     Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
 )]
-#[reflect_value(Hash, Serialize, Deserialize)]
+#[reflect_value(Hash, PartialEq, Serialize, Deserialize)]
 pub struct SpeciesId(u8);
 
 const UNDEFINED_SPECIES_ID: u8 = u8::MAX;
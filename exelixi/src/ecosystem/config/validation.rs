@@ -0,0 +1,549 @@
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::Path;
+
+use super::*;
+
+// Maximum number of species, the last SpeciesId value being reserved for undefined species.
+pub const MAX_SPECIES: usize = u8::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigIssueLevel {
+    // The config cannot be used.
+    Error,
+    // The config can be used, but is likely not what was intended.
+    Warning,
+}
+
+// A problem found in a config, with the path of the field it is about.
+// ex: species[Herbivore].eye.visible
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub level: ConfigIssueLevel,
+    pub path: String,
+    pub message: String,
+}
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            ConfigIssueLevel::Error => "error",
+            ConfigIssueLevel::Warning => "warning",
+        };
+        write!(f, "{level}: {}: {}", self.path, self.message)
+    }
+}
+
+// All the problems found in a config.
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub issues: Vec<ConfigIssue>,
+}
+impl ConfigReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+    pub fn errors(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.level == ConfigIssueLevel::Error)
+    }
+    pub fn warnings(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.level == ConfigIssueLevel::Warning)
+    }
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(ConfigIssueLevel::Error, path.into(), message.into());
+    }
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(ConfigIssueLevel::Warning, path.into(), message.into());
+    }
+    fn push(&mut self, level: ConfigIssueLevel, path: String, message: String) {
+        self.issues.push(ConfigIssue {
+            level,
+            path,
+            message,
+        });
+    }
+}
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            writeln!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl UserEcosystemConfig {
+    // Checks the whole config, collecting every problem instead of stopping at the first one.
+    pub fn validate(&self) -> ConfigReport {
+        let mut report = ConfigReport::default();
+        let environment = &self.environment;
+        if environment.width <= 0 || environment.height <= 0 {
+            report.error(
+                "environment",
+                format!(
+                    "size must be positive, got {}x{}",
+                    environment.width, environment.height
+                ),
+            );
+        }
+        if self.statistics.aggregation_rate == Some(0) {
+            report.error("statistics.aggregation_rate", "must be at least 1");
+        }
+        if self
+            .statistics
+            .max_length
+            .is_some_and(|max_length| max_length < 3)
+        {
+            report.error("statistics.max_length", "must be at least 3");
+        }
+        if self.species.is_empty() {
+            report.error("species", "no species defined");
+        }
+        if self.species.len() > MAX_SPECIES {
+            report.error(
+                "species",
+                format!(
+                    "at most {MAX_SPECIES} species are supported, got {}",
+                    self.species.len()
+                ),
+            );
+        }
+        let mut names = HashSet::new();
+        for species in self.species.iter() {
+            if !names.insert(species.name.as_str()) {
+                report.error(
+                    format!("species[{}]", species.name),
+                    "species name is used more than once",
+                );
+            }
+        }
+        for species in self.species.iter() {
+            validate_species(species, &names, &mut report);
+        }
+        report
+    }
+}
+
+// Report of the config file, or the reason it could not be read.
+pub fn check_config_file(path: &Path) -> Result<ConfigReport, String> {
+    let ron_string = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let user_config =
+        ron::from_str::<UserEcosystemConfig>(&ron_string).map_err(|err| err.to_string())?;
+    Ok(user_config.validate())
+}
+
+fn validate_species(species: &SpeciesConfig, names: &HashSet<&str>, report: &mut ConfigReport) {
+    let path = format!("species[{}]", species.name);
+    let body = &species.body;
+    if body.maximum_energy <= 0.0 {
+        report.error(format!("{path}.body.maximum_energy"), "must be positive");
+    }
+    if body.starting_energy > body.maximum_energy {
+        report.warning(
+            format!("{path}.body.starting_energy"),
+            format!(
+                "{} is above maximum_energy {}",
+                body.starting_energy, body.maximum_energy
+            ),
+        );
+    }
+    if let Some(eye) = &species.eye {
+        let path = format!("{path}.eye");
+        validate_gene_value(&format!("{path}.fov_angle"), &eye.fov_angle, report);
+        validate_gene_value(&format!("{path}.fov_range"), &eye.fov_range, report);
+        validate_gene_value(&format!("{path}.n_cells"), &eye.n_cells, report);
+        let (min, max) = eye.fov_angle.bounds();
+        if min <= 0.0 || max > 2.0 * PI {
+            report.warning(
+                format!("{path}.fov_angle"),
+                format!("{min}..{max} is not within ]0, 2*PI]"),
+            );
+        }
+        if eye.n_cells.bounds().0 == 0 {
+            report.error(format!("{path}.n_cells"), "an eye needs at least 1 cell");
+        }
+        validate_species_names(&format!("{path}.visible"), &eye.visible, names, report);
+        if eye.visible.is_empty() {
+            report.warning(format!("{path}.visible"), "the eye sees no species");
+        }
+    }
+    if let Some(locomotion) = &species.locomotion {
+        let path = format!("{path}.locomotion.linear");
+        match locomotion.linear {
+            ConfigValue::Fixed(_) => (),
+            ConfigValue::Neuron { min, max } => validate_bounds(&path, min, max, report),
+            ConfigValue::Gene { .. } => report.error(path, "must be Fixed or Neuron, not Gene"),
+        }
+    }
+    if let Some(mouth) = &species.mouth {
+        let path = format!("{path}.mouth.edible");
+        validate_species_names(&path, &mouth.edible, names, report);
+        if mouth.edible.is_empty() {
+            report.warning(path, "the mouth can eat no species");
+        }
+    }
+    let is_birth = matches!(species.reproduction, ReproductionConfig::Birth { .. });
+    if is_birth && species.uterus.is_none() {
+        report.error(
+            format!("{path}.uterus"),
+            "a Birth reproduction needs a uterus",
+        );
+    }
+    if !is_birth && species.uterus.is_some() {
+        report.warning(
+            format!("{path}.uterus"),
+            "a uterus is only used by a Birth reproduction",
+        );
+    }
+    validate_reproduction(&format!("{path}.reproduction"), species, report);
+    validate_brain(&format!("{path}.brain"), &species.brain, report);
+    if let Some(gene_pool) = &species.gene_pool {
+        validate_chance(
+            &format!("{path}.gene_pool.mutation_chance"),
+            gene_pool.mutation_chance,
+            report,
+        );
+    }
+}
+
+fn validate_brain(path: &str, brain: &BrainConfig, report: &mut ConfigReport) {
+    match brain.kind {
+        BrainKind::Layered => {
+            for (i, layer) in brain.hidden_layers.iter().enumerate() {
+                let path = format!("{path}.hidden_layers[{i}]");
+                match *layer {
+                    LayerSize::Sensors(factor) | LayerSize::Actuators(factor) if factor <= 0.0 => {
+                        report.error(path, format!("factor {factor} must be positive"))
+                    }
+                    LayerSize::Neurons(0) => report.error(path, "a layer needs at least 1 neuron"),
+                    _ => (),
+                }
+            }
+            for &layer in brain.recurrent_layers.iter() {
+                if layer >= brain.hidden_layers.len() {
+                    report.error(
+                        format!("{path}.recurrent_layers"),
+                        format!(
+                            "layer {layer} does not exist, there are {} hidden layers",
+                            brain.hidden_layers.len()
+                        ),
+                    );
+                }
+            }
+        }
+        BrainKind::Neat {
+            add_node_chance,
+            add_connection_chance,
+        } => {
+            validate_chance(
+                &format!("{path}.kind.add_node_chance"),
+                add_node_chance,
+                report,
+            );
+            validate_chance(
+                &format!("{path}.kind.add_connection_chance"),
+                add_connection_chance,
+                report,
+            );
+        }
+    }
+    validate_chance(
+        &format!("{path}.connection_toggle_chance"),
+        brain.connection_toggle_chance,
+        report,
+    );
+}
+
+fn validate_reproduction(path: &str, species: &SpeciesConfig, report: &mut ConfigReport) {
+    match &species.reproduction {
+        ReproductionConfig::AutoSpawn { spawn_rate, .. } => {
+            if *spawn_rate < 0.0 {
+                report.error(format!("{path}.spawn_rate"), "must not be negative");
+            }
+        }
+        ReproductionConfig::GenerationEvolution {
+            generation_length,
+            fertility_rate,
+            mutation_chance,
+            mutation_amplitude,
+            crossover,
+            mutation,
+            selection,
+            niching,
+            fitness,
+            ..
+        } => {
+            if *generation_length == 0 {
+                report.error(format!("{path}.generation_length"), "must be at least 1");
+            }
+            if *fertility_rate <= 0.0 {
+                report.error(format!("{path}.fertility_rate"), "must be positive");
+            }
+            validate_chance(&format!("{path}.mutation_chance"), *mutation_chance, report);
+            validate_operators(path, *mutation_amplitude, crossover, mutation, report);
+            match selection {
+                SelectionConfig::Tournament { size: 0 } => {
+                    report.error(format!("{path}.selection.size"), "must be at least 1")
+                }
+                SelectionConfig::Truncation { ratio } if *ratio <= 0.0 || *ratio > 1.0 => report
+                    .error(
+                        format!("{path}.selection.ratio"),
+                        format!("{ratio} is not within ]0, 1]"),
+                    ),
+                _ => (),
+            }
+            match *niching {
+                Some(NichingConfig::FitnessSharing { radius, alpha }) => {
+                    validate_positive(&format!("{path}.niching.radius"), radius, report);
+                    validate_positive(&format!("{path}.niching.alpha"), alpha, report);
+                }
+                Some(NichingConfig::Clustering { threshold }) => {
+                    validate_positive(&format!("{path}.niching.threshold"), threshold, report);
+                }
+                None => (),
+            }
+            if fitness.objectives.is_empty() {
+                report.error(format!("{path}.fitness.objectives"), "no objective defined");
+            }
+            if fitness.objectives.contains(&FitnessObjective::Novelty) {
+                validate_chance(
+                    &format!("{path}.fitness.novelty.archive_chance"),
+                    fitness.novelty.archive_chance,
+                    report,
+                );
+            }
+        }
+        ReproductionConfig::Birth {
+            minimum_energy_pct,
+            child_energy_pct,
+            mutation_chance,
+            mutation_amplitude,
+            crossover,
+            mutation,
+            ..
+        } => {
+            validate_chance(
+                &format!("{path}.minimum_energy_pct"),
+                *minimum_energy_pct,
+                report,
+            );
+            validate_chance(
+                &format!("{path}.child_energy_pct"),
+                *child_energy_pct,
+                report,
+            );
+            validate_chance(&format!("{path}.mutation_chance"), *mutation_chance, report);
+            validate_operators(path, *mutation_amplitude, crossover, mutation, report);
+        }
+    }
+}
+
+fn validate_operators(
+    path: &str,
+    mutation_amplitude: f32,
+    crossover: &CrossoverConfig,
+    mutation: &MutationConfig,
+    report: &mut ConfigReport,
+) {
+    if mutation_amplitude < 0.0 {
+        report.error(format!("{path}.mutation_amplitude"), "must not be negative");
+    }
+    if let CrossoverConfig::ArithmeticBlend { alpha } = *crossover {
+        if alpha < 0.0 {
+            report.error(format!("{path}.crossover.alpha"), "must not be negative");
+        }
+    }
+    if let MutationConfig::SelfAdaptive { learning_rate } = *mutation {
+        if learning_rate < 0.0 {
+            report.error(
+                format!("{path}.mutation.learning_rate"),
+                "must not be negative",
+            );
+        }
+        // mutation_amplitude is the initial step size.
+        let minimum_step = ga::SelfAdaptiveMutation::MINIMUM_STEP;
+        if mutation_amplitude < minimum_step {
+            report.error(
+                format!("{path}.mutation_amplitude"),
+                format!(
+                    "initial step {mutation_amplitude} is below the minimum step {minimum_step}"
+                ),
+            );
+        }
+    }
+}
+
+// Values decoded from genes can only be Fixed or Gene.
+fn validate_gene_value<T: PartialOrd + fmt::Display + Copy>(
+    path: &str,
+    value: &ConfigValue<T>,
+    report: &mut ConfigReport,
+) {
+    match *value {
+        ConfigValue::Fixed(_) => (),
+        ConfigValue::Gene { min, max } => validate_bounds(path, min, max, report),
+        ConfigValue::Neuron { .. } => report.error(path, "must be Fixed or Gene, not Neuron"),
+    }
+}
+
+fn validate_bounds<T: PartialOrd + fmt::Display>(
+    path: &str,
+    min: T,
+    max: T,
+    report: &mut ConfigReport,
+) {
+    if min > max {
+        report.error(path, format!("min {min} is greater than max {max}"));
+    }
+}
+
+fn validate_positive(path: &str, value: f32, report: &mut ConfigReport) {
+    if value <= 0.0 {
+        report.error(path, format!("{value} must be positive"));
+    }
+}
+
+fn validate_chance(path: &str, chance: f32, report: &mut ConfigReport) {
+    if !(0.0..=1.0).contains(&chance) {
+        report.error(path, format!("{chance} is not within [0, 1]"));
+    }
+}
+
+fn validate_species_names(
+    path: &str,
+    species: &[String],
+    names: &HashSet<&str>,
+    report: &mut ConfigReport,
+) {
+    for name in species.iter() {
+        if !names.contains(name.as_str()) {
+            report.error(path, format!("unknown species {name}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_configs_are_valid() {
+        let configs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../configs");
+        for entry in std::fs::read_dir(configs).unwrap() {
+            let path = entry.unwrap().path();
+            let report = check_config_file(&path).unwrap();
+            assert!(!report.has_errors(), "{path:?}:\n{report}");
+        }
+    }
+
+    // Paths of the errors of the default config, once changed by f.
+    fn error_paths(f: impl FnOnce(&mut UserEcosystemConfig)) -> Vec<String> {
+        let mut config: UserEcosystemConfig =
+            ron::from_str(include_str!("../../../../configs/default.ron")).unwrap();
+        f(&mut config);
+        config
+            .validate()
+            .errors()
+            .map(|issue| issue.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn invalid_configs_are_reported() {
+        let herbivore = "species[Herbivore]";
+        assert_eq!(error_paths(|_| ()), Vec::<String>::new());
+        let cases: Vec<(&str, fn(&mut UserEcosystemConfig))> = vec![
+            ("eye.visible", |config| {
+                config.species[0].eye.as_mut().unwrap().visible = vec!["Rock".to_string()]
+            }),
+            ("mouth.edible", |config| {
+                config.species[0].mouth.as_mut().unwrap().edible = vec!["Rock".to_string()]
+            }),
+            ("eye.fov_angle", |config| {
+                config.species[0].eye.as_mut().unwrap().fov_angle =
+                    ConfigValue::Neuron { min: 1.0, max: 2.0 }
+            }),
+            ("eye.fov_range", |config| {
+                config.species[0].eye.as_mut().unwrap().fov_range = ConfigValue::Gene {
+                    min: 400.0,
+                    max: 70.0,
+                }
+            }),
+            ("uterus", |config| {
+                config.species[0].reproduction = ReproductionConfig::Birth {
+                    minimum_age: 100,
+                    minimum_energy_pct: 0.5,
+                    child_energy_pct: 0.2,
+                    mutation_chance: 0.01,
+                    mutation_amplitude: 0.3,
+                    crossover: CrossoverConfig::default(),
+                    mutation: MutationConfig::default(),
+                }
+            }),
+            ("reproduction.generation_length", |config| {
+                if let ReproductionConfig::GenerationEvolution {
+                    generation_length, ..
+                } = &mut config.species[0].reproduction
+                {
+                    *generation_length = 0;
+                }
+            }),
+            ("reproduction.mutation.learning_rate", |config| {
+                if let ReproductionConfig::GenerationEvolution { mutation, .. } =
+                    &mut config.species[0].reproduction
+                {
+                    *mutation = MutationConfig::SelfAdaptive {
+                        learning_rate: -0.1,
+                    };
+                }
+            }),
+            ("reproduction.mutation_amplitude", |config| {
+                if let ReproductionConfig::GenerationEvolution {
+                    mutation,
+                    mutation_amplitude,
+                    ..
+                } = &mut config.species[0].reproduction
+                {
+                    *mutation = MutationConfig::SelfAdaptive { learning_rate: 0.1 };
+                    *mutation_amplitude = 0.0;
+                }
+            }),
+            ("reproduction.niching.radius", |config| {
+                if let ReproductionConfig::GenerationEvolution { niching, .. } =
+                    &mut config.species[0].reproduction
+                {
+                    *niching = Some(NichingConfig::FitnessSharing {
+                        radius: 0.0,
+                        alpha: 1.0,
+                    });
+                }
+            }),
+            ("brain.hidden_layers[0]", |config| {
+                config.species[0].brain.hidden_layers = vec![LayerSize::Neurons(0)]
+            }),
+            ("brain.kind.add_node_chance", |config| {
+                config.species[0].brain.kind = BrainKind::Neat {
+                    add_node_chance: 2.0,
+                    add_connection_chance: 0.1,
+                }
+            }),
+        ];
+        for (field, change) in cases {
+            assert_eq!(error_paths(change), vec![format!("{herbivore}.{field}")]);
+        }
+        let too_many_species = error_paths(|config| {
+            let plant = config.species[1].clone();
+            for i in 0..MAX_SPECIES {
+                config.species.push(SpeciesConfig {
+                    name: format!("Plant{i}"),
+                    ..plant.clone()
+                });
+            }
+        });
+        assert_eq!(too_many_species, vec!["species".to_string()]);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::ecosystem::*;
+
+// File format of the organisms dump of an inspection.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum OrganismsFormat {
+    Csv,
+    Json,
+}
+impl OrganismsFormat {
+    // '.json' for Json, Csv for any other extension.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => OrganismsFormat::Json,
+            _ => OrganismsFormat::Csv,
+        }
+    }
+}
+
+// One organism of an inspected save.
+#[derive(Serialize)]
+pub struct OrganismRecord {
+    pub species: String,
+    pub age: u32,
+    pub offspring: u32,
+    pub energy: f32,
+    pub x: f32,
+    pub y: f32,
+    pub genes: Vec<f32>,
+}
+
+// Mean and variance of one gene among the organisms of a species.
+pub struct GeneStatistic {
+    pub locus: String,
+    pub mean: f32,
+    pub variance: f32,
+}
+
+pub struct SpeciesInspection {
+    pub name: String,
+    pub population: usize,
+    pub generation: u32,
+    pub energy_total: f32,
+    pub energy_min: f32,
+    pub energy_max: f32,
+    pub genes: Vec<GeneStatistic>,
+    // Chromosomes left out of the gene statistics, as they do not match the species layout.
+    pub mismatched_chromosomes: usize,
+}
+
+// Summary of a saved ecosystem, read without running it.
+pub struct SaveInspection {
+    pub header: SaveHeader,
+    pub steps: u32,
+    pub config: EcosystemConfig,
+    pub species: Vec<SpeciesInspection>,
+    pub organisms: Vec<OrganismRecord>,
+}
+impl SaveInspection {
+    // Loads the save into a world with only the ecosystem plugin, no step is run.
+    pub fn from_file(path: &Path) -> Result<Self, SaveError> {
+        let data = std::fs::read(path)?;
+        let (header, _) = SaveHeader::split(&data);
+        let mut app = App::new();
+        app.add_plugins(EcosystemPlugin {
+            seed: Some(0),
+            config_path: None,
+            override_stats_start: None,
+            override_stats_rate: None,
+            statistics_outputs: vec![],
+            statistics_console: false,
+            lineage_output: None,
+        });
+        deserialize_ecosystem(&data, app.world_mut())?;
+        Ok(Self::from_world(header, app.world_mut()))
+    }
+    fn from_world(header: SaveHeader, world: &mut World) -> Self {
+        let config = world.resource::<EcosystemConfig>().clone();
+        let runtime = world.resource::<EcosystemRuntime>();
+        let steps = runtime.steps;
+        let generation = runtime.generation.clone();
+        let mut per_species = BTreeMap::<SpeciesId, Vec<(&Organism, &Body, &Position)>>::new();
+        let mut query = world.query::<(&Organism, &Body, &Position)>();
+        for (organism, body, position) in query.iter(world) {
+            per_species
+                .entry(organism.species)
+                .or_default()
+                .push((organism, body, position));
+        }
+        let species = config
+            .species
+            .values()
+            .map(|species_config| {
+                let organisms = per_species
+                    .get(&species_config.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let energies = organisms.iter().map(|(_, body, _)| body.energy);
+                let (genes, mismatched_chromosomes) = gene_statistics(species_config, organisms);
+                SpeciesInspection {
+                    name: species_config.name.clone(),
+                    population: organisms.len(),
+                    generation: generation.get(&species_config.id).copied().unwrap_or(0),
+                    energy_total: energies.clone().sum(),
+                    energy_min: energies.clone().reduce(f32::min).unwrap_or(0.0),
+                    energy_max: energies.reduce(f32::max).unwrap_or(0.0),
+                    genes,
+                    mismatched_chromosomes,
+                }
+            })
+            .collect();
+        let organisms = per_species
+            .values()
+            .flatten()
+            .map(|(organism, body, position)| OrganismRecord {
+                species: config.species[&organism.species].name.clone(),
+                age: organism.age,
+                offspring: organism.offspring,
+                energy: body.energy,
+                x: position.x,
+                y: position.y,
+                genes: organism.chromosome.iter().copied().collect(),
+            })
+            .collect();
+        Self {
+            header,
+            steps,
+            config,
+            species,
+            organisms,
+        }
+    }
+    pub fn sprint(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "Save format version {} ({:?}), written by exelixi {}",
+            self.header.format_version, self.header.format, self.header.crate_version
+        );
+        let _ = writeln!(s, "Steps: {:6}", self.steps);
+        for species in self.species.iter() {
+            let _ = writeln!(
+                s,
+                "  {:10} - Size:{:5} Generation:{:4} Energy:{:9.0} Mean:{:7.2} Min:{:7.2} Max:{:7.2}",
+                species.name,
+                species.population,
+                species.generation,
+                species.energy_total,
+                species.energy_total / species.population.max(1) as f32,
+                species.energy_min,
+                species.energy_max,
+            );
+        }
+        let _ = writeln!(s, "Genes:");
+        for species in self
+            .species
+            .iter()
+            .filter(|species| !species.genes.is_empty() || species.mismatched_chromosomes > 0)
+        {
+            let _ = writeln!(s, "  {}", species.name);
+            if species.mismatched_chromosomes > 0 {
+                let _ = writeln!(
+                    s,
+                    "    {} chromosomes left out, not matching the species layout",
+                    species.mismatched_chromosomes
+                );
+            }
+            for gene in species.genes.iter() {
+                let _ = writeln!(
+                    s,
+                    "    {:30} Mean:{:9.4} Variance:{:9.4}",
+                    gene.locus, gene.mean, gene.variance
+                );
+            }
+        }
+        let config = ron::ser::to_string_pretty(&self.config, ron::ser::PrettyConfig::default())
+            .unwrap_or_else(|err| format!("config could not be printed: {err}"));
+        let _ = writeln!(s, "Config:\n{config}");
+        s
+    }
+    pub fn write_organisms(&self, path: &Path, format: OrganismsFormat) -> Result<(), SaveError> {
+        let data = match format {
+            OrganismsFormat::Csv => {
+                let mut s = "species,age,offspring,energy,x,y,genes\n".to_string();
+                for organism in self.organisms.iter() {
+                    let genes = organism
+                        .genes
+                        .iter()
+                        .map(f32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let _ = writeln!(
+                        s,
+                        "{},{},{},{},{},{},{genes}",
+                        organism.species,
+                        organism.age,
+                        organism.offspring,
+                        organism.energy,
+                        organism.x,
+                        organism.y
+                    );
+                }
+                s
+            }
+            OrganismsFormat::Json => serde_json::to_string_pretty(&self.organisms)
+                .map_err(|err| SaveError::Serialization(err.to_string()))?,
+        };
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+// Statistics of each locus of the species layout, over the organisms with a chromosome,
+// and the number of chromosomes left out as their length differs from the layout.
+fn gene_statistics(
+    config: &SpeciesConfig,
+    organisms: &[(&Organism, &Body, &Position)],
+) -> (Vec<GeneStatistic>, usize) {
+    let (chromosomes, mismatched): (Vec<_>, Vec<_>) = organisms
+        .iter()
+        .map(|(organism, _, _)| &organism.chromosome)
+        .filter(|chromosome| !chromosome.is_empty())
+        .partition(|chromosome| chromosome.len() == config.chromosome_layout.len());
+    if chromosomes.is_empty() {
+        return (vec![], mismatched.len());
+    }
+    let n = chromosomes.len() as f32;
+    let genes = config
+        .chromosome_layout
+        .iter()
+        .enumerate()
+        .map(|(i, locus)| {
+            let mean = chromosomes
+                .iter()
+                .map(|chromosome| chromosome[i])
+                .sum::<f32>()
+                / n;
+            let variance = chromosomes
+                .iter()
+                .map(|chromosome| (chromosome[i] - mean).powi(2))
+                .sum::<f32>()
+                / n;
+            GeneStatistic {
+                locus: locus.name.clone(),
+                mean,
+                variance,
+            }
+        })
+        .collect();
+    (genes, mismatched.len())
+}
+
+// Prints the inspection of the save, and dumps its organisms when a path is given.
+pub fn inspect_save(
+    path: &Path,
+    organisms: Option<(&Path, OrganismsFormat)>,
+) -> Result<(), SaveError> {
+    let inspection = SaveInspection::from_file(path)?;
+    println!("{}", inspection.sprint());
+    if let Some((organisms_path, format)) = organisms {
+        inspection.write_organisms(organisms_path, format)?;
+        println!(
+            "{} organisms written to '{organisms_path:?}'",
+            inspection.organisms.len()
+        );
+    }
+    Ok(())
+}
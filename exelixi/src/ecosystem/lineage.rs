@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::ecosystem::*;
+
+// Event of the lineage of the organisms, written as one JSON object per line.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event")]
+pub enum LineageEvent {
+    Birth {
+        step: u32,
+        id: OrganismId,
+        species: String,
+        origin: BirthOrigin,
+        parents: Vec<OrganismId>,
+    },
+    Death {
+        step: u32,
+        id: OrganismId,
+        cause: DeathCause,
+    },
+    // Organism already alive when the log was started, as when continuing a save.
+    Alive {
+        step: u32,
+        id: OrganismId,
+        species: String,
+        born: u32,
+        parents: Vec<OrganismId>,
+    },
+}
+
+// Destination of the lineage events, none are recorded without a file.
+#[derive(Resource, Default)]
+pub struct LineageLog {
+    writer: Option<BufWriter<File>>,
+    // Whether the organisms alive when the log was created were written.
+    started: bool,
+}
+impl LineageLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: Some(BufWriter::new(File::create(path)?)),
+            started: false,
+        })
+    }
+    // Writes the organisms alive before the first recorded event.
+    pub fn start<'a>(
+        &mut self,
+        step: u32,
+        config: &EcosystemConfig,
+        organisms: impl Iterator<Item = &'a Organism>,
+    ) {
+        if self.started || self.writer.is_none() {
+            return;
+        }
+        self.started = true;
+        for organism in organisms {
+            self.write(&LineageEvent::Alive {
+                step,
+                id: organism.id,
+                species: config.species[&organism.species].name.clone(),
+                born: step.saturating_sub(organism.age),
+                parents: organism.parents.clone(),
+            });
+        }
+    }
+    pub fn write(&mut self, event: &LineageEvent) {
+        if let Some(writer) = &mut self.writer {
+            let written = serde_json::to_writer(&mut *writer, event)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(writer));
+            if let Err(err) = written {
+                error!("Lineage event could not be written, lineage recording stopped: {err}");
+                self.writer = None;
+            }
+        }
+    }
+    // Flushed at each step, so that the file can be read while running.
+    pub fn flush(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(err) = writer.flush() {
+                error!("Lineage events could not be written, lineage recording stopped: {err}");
+                self.writer = None;
+            }
+        }
+    }
+}
@@ -1,23 +1,87 @@
-use bevy::scene::serde::SceneDeserializer;
-use serde::de::DeserializeSeed;
-
 use crate::ecosystem::*;
+use bevy::reflect::TypeRegistry;
 
-pub fn load_ecosystem_from_file(path: &PathBuf, world: &mut World) {
-    match std::fs::read_to_string(path) {
-        Ok(data) => {
-            let type_registry = world.resource::<AppTypeRegistry>().0.clone();
-            let mut deserializer = ron::de::Deserializer::from_str(&data).unwrap();
-            let scene_deserializer = SceneDeserializer {
-                type_registry: &type_registry.read(),
-            };
-            let scene = scene_deserializer.deserialize(&mut deserializer).unwrap();
-            let mut entity_map = bevy::ecs::entity::EntityHashMap::default();
-            scene.write_to_world(world, &mut entity_map).unwrap();
-            info!("Ecosystem loaded from '{path:?}'");
-        }
-        Err(err) => {
-            error!("Ecosystem at path '{path:?}' could not be loaded: {err:?}");
+pub fn load_ecosystem_from_file(path: &PathBuf, world: &mut World) -> Result<(), SaveError> {
+    let data = std::fs::read(path)?;
+    deserialize_ecosystem(&data, world)?;
+    info!("Ecosystem loaded from '{path:?}'");
+    Ok(())
+}
+
+// Replaces the ecosystem of the world by the saved one, migrated to the current format.
+// The world is left untouched when the save cannot be read or migrated.
+pub fn deserialize_ecosystem(data: &[u8], world: &mut World) -> Result<(), SaveError> {
+    let (header, data) = SaveHeader::split(data);
+    if header.format_version > SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(header));
+    }
+    if header.format_version < SAVE_FORMAT_VERSION {
+        info!(
+            "Migrating save from format version {} (exelixi {})",
+            header.format_version, header.crate_version
+        );
+    }
+    let data = migrate_text(&header, data)?;
+    let type_registry = world.resource::<AppTypeRegistry>().0.clone();
+    let mut scene = header.format.decode(&data, &type_registry.read())?;
+    migrate_scene(header.format_version, &mut scene)?;
+    // Organisms are spawned with their Organism component last, so that, as during the simulation,
+    // the archetypes they go through while being built never contain other organisms.
+    // Organisms then end up in the same archetypes order as when saved.
+    for entity in scene.entities.iter_mut() {
+        entity
+            .components
+            .sort_by_key(|component| component.represents::<Organism>());
+    }
+    let has_flows = scene
+        .resources
+        .iter()
+        .any(|resource| resource.represents::<StatisticsFlows>());
+    check_complete(&scene, &type_registry.read())?;
+    let mut entity_map = bevy::ecs::entity::EntityHashMap::default();
+    scene
+        .write_to_world(world, &mut entity_map)
+        .map_err(|err| SaveError::Spawn(err.to_string()))?;
+    // Saves from before the flows were saved start with empty ones.
+    if !has_flows {
+        world.insert_resource(StatisticsFlows::default());
+    }
+    // Chromosome layouts are derived from the species config, older saves do not have them.
+    let mut config = world.resource_mut::<EcosystemConfig>();
+    for species_config in config.species.values_mut() {
+        species_config.chromosome_layout = OrganismIndividual::chromosome_layout(species_config);
+    }
+    // Rebuild the 'compiled' resources before any step runs on the loaded config.
+    let config = world.resource::<EcosystemConfig>();
+    let generation_evolutions = GenerationEvolutions::new(config);
+    let gene_pools = GenePools::new(config, &type_registry.read());
+    world.insert_resource(generation_evolutions);
+    world.insert_resource(gene_pools);
+    Ok(())
+}
+
+// Checks that every saved component and resource has all the fields of its type,
+// as writing an incomplete one to the world panics.
+fn check_complete(scene: &DynamicScene, type_registry: &TypeRegistry) -> Result<(), SaveError> {
+    let values = scene.resources.iter().chain(
+        scene
+            .entities
+            .iter()
+            .flat_map(|entity| entity.components.iter()),
+    );
+    for value in values {
+        let Some(type_info) = value.get_represented_type_info() else {
+            continue;
+        };
+        let complete = type_registry
+            .get_type_data::<ReflectFromReflect>(type_info.type_id())
+            .is_none_or(|from_reflect| from_reflect.from_reflect(value.as_reflect()).is_some());
+        if !complete {
+            return Err(SaveError::Deserialization(format!(
+                "incomplete {}",
+                type_info.type_path()
+            )));
         }
     }
+    Ok(())
 }
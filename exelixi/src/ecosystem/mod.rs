@@ -1,12 +1,18 @@
 mod config;
+mod inspect;
 mod kdtree;
+mod lineage;
 mod load;
 mod organism;
+mod phylogeny;
 mod position;
 mod runtime;
 mod save;
+mod save_format;
 mod schedule;
 mod stats;
+mod stats_sink;
+mod transplant;
 
 pub use bevy::log;
 pub use bevy::prelude::*;
@@ -16,14 +22,20 @@ pub use serde::{Deserialize, Serialize};
 
 use bevy_rand::prelude::*;
 pub use config::*;
+pub use inspect::*;
 pub use kdtree::*;
+pub use lineage::*;
 pub use load::*;
 pub use organism::*;
+pub use phylogeny::*;
 pub use position::*;
 pub use runtime::*;
 pub use save::*;
+pub use save_format::*;
 pub use schedule::*;
 pub use stats::*;
+pub use stats_sink::*;
+pub use transplant::*;
 
 use std::path::PathBuf;
 
@@ -32,6 +44,12 @@ pub struct EcosystemPlugin {
     pub config_path: Option<PathBuf>,
     pub override_stats_start: Option<u32>,
     pub override_stats_rate: Option<u32>,
+    // Files the statistics are written to, in the format of their extension.
+    pub statistics_outputs: Vec<PathBuf>,
+    // Print the statistics on the standard output.
+    pub statistics_console: bool,
+    // File the lineage events are written to, as JSON Lines.
+    pub lineage_output: Option<PathBuf>,
 }
 impl Plugin for EcosystemPlugin {
     fn build(&self, app: &mut App) {
@@ -53,6 +71,9 @@ impl Plugin for EcosystemPlugin {
             .register_type::<CellSensors>()
             .register_type::<Position>()
             .register_type::<Organism>()
+            .register_type::<OrganismId>()
+            .register_type::<Vec<OrganismId>>()
+            .register_type::<Option<OrganismId>>()
             .register_type::<Body>()
             .register_type::<Brain>()
             .register_type::<Eye>()
@@ -60,21 +81,74 @@ impl Plugin for EcosystemPlugin {
             .register_type::<Locomotion>()
             .register_type::<Mouth>()
             .register_type::<Uterus>()
+            .register_type::<OrganismsLifecycle>()
+            .register_type::<OrganismBirth>()
+            .register_type::<NoveltyArchives>()
+            .register_type::<OrganismExport>()
+            .register_type::<GenePool>()
+            .register_type::<Vec<ga::Chromosome>>()
+            .register_type::<Option<Eye>>()
+            .register_type::<Option<Brain>>()
             .register_type::<nn::Network>()
             .register_type::<nn::Layer>()
             .register_type::<nn::Neuron>()
+            .register_type::<nn::Activation>()
+            .register_type::<nn::GraphNetwork>()
+            .register_type::<nn::GraphConnection>()
+            .register_type::<ga::Chromosome>()
+            .register_type::<Option<ga::Chromosome>>()
+            .register_type::<ga::NoveltyArchive>()
+            .register_type::<ga::NeatGenome>()
+            .register_type::<ga::NodeGene>()
+            .register_type::<ga::ConnectionGene>()
+            .register_type::<ga::ChromosomeLayout>()
+            .register_type::<ga::Locus>()
+            .register_type::<ga::GeneKind>()
+            .register_type::<Vec<ga::Locus>>()
+            .register_type::<BrainNetwork>()
+            .register_type::<BrainKind>()
             .register_type::<EcosystemConfig>()
             .register_type::<EcosystemRuntime>()
             .register_type::<EcosystemStatistics>()
             .register_type::<SpeciesStatistics>()
             .register_type::<SpeciesStatistic>()
+            .register_type::<SpeciesFlows>()
+            .register_type::<StatisticsFlows>()
+            .register_type::<std::collections::BTreeMap<SpeciesId, SpeciesFlows>>()
+            .register_type::<SpeciesTraits>()
+            .register_type::<TraitDistribution>()
+            .register_type::<Option<TraitDistribution>>()
+            .register_type::<StatisticEnvelope>()
+            .register_type::<Option<StatisticEnvelope>>()
+            .register_type::<DeathCause>()
+            .register_type::<BirthOrigin>()
+            .register_type::<Vec<(Entity, DeathCause)>>()
+            .register_type::<Vec<(u32, Vec<String>)>>()
             .register_type::<SpeciesConfig>()
             .register_type::<BodyConfig>()
             .register_type::<EyeConfig>()
+            .register_type::<BrainConfig>()
+            .register_type::<LayerSize>()
+            .register_type::<GenePoolConfig>()
+            .register_type::<Option<GenePoolConfig>>()
+            .register_type::<SelectionConfig>()
+            .register_type::<CrossoverConfig>()
+            .register_type::<MutationConfig>()
+            .register_type::<NichingConfig>()
+            .register_type::<FitnessConfig>()
+            .register_type::<FitnessObjective>()
+            .register_type::<NoveltyConfig>()
+            .register_type::<Vec<FitnessObjective>>()
             .register_type::<Vec<SpeciesId>>()
             .register_type::<Vec<nn::Layer>>()
             .register_type::<Vec<nn::Neuron>>()
-            .register_type::<Vec<f32>>();
+            .register_type::<Vec<f32>>()
+            .register_type::<Vec<Entity>>()
+            .register_type::<Vec<OrganismBirth>>()
+            .register_type::<Option<Position>>()
+            .register_type::<Option<f32>>()
+            .register_type::<std::collections::BTreeMap<SpeciesId, ga::NoveltyArchive>>()
+            .register_type::<Vec<Vec<f32>>>();
 
         let ecosystem_config = EcosystemConfig::from_path(self.config_path.clone())
             .with_statistics_aggregation_override(
@@ -83,7 +157,33 @@ impl Plugin for EcosystemPlugin {
             );
         app.insert_resource(EcosystemRuntime::new(&ecosystem_config));
         app.insert_resource(EcosystemStatistics::new(&ecosystem_config));
+        app.insert_resource(StatisticsFlows::default());
+        let mut statistics_sinks = StatisticsSinks { sinks: vec![] };
+        if self.statistics_console {
+            statistics_sinks.sinks.push(Box::new(ConsoleSink));
+        }
+        for path in self.statistics_outputs.iter() {
+            match statistics_file_sink(path) {
+                Ok(sink) => statistics_sinks.sinks.push(sink),
+                Err(err) => error!("Statistics output '{path:?}' could not be created: {err}"),
+            }
+        }
+        app.insert_resource(statistics_sinks);
+        let lineage_log = match &self.lineage_output {
+            Some(path) => LineageLog::create(path).unwrap_or_else(|err| {
+                error!("Lineage output '{path:?}' could not be created: {err}");
+                LineageLog::default()
+            }),
+            None => LineageLog::default(),
+        };
+        app.insert_resource(lineage_log);
         app.insert_resource(GenerationEvolutions::new(&ecosystem_config));
+        app.insert_resource(NoveltyArchives::default());
+        let gene_pools = GenePools::new(
+            &ecosystem_config,
+            &app.world().resource::<AppTypeRegistry>().read(),
+        );
+        app.insert_resource(gene_pools);
         app.insert_resource(ecosystem_config);
 
         app.insert_resource(OrganismKdTree::default());
@@ -1,22 +1,57 @@
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntitiesResource;
 use lib_genetic_algorithm::{Chromosome, Individual};
 
 use crate::ecosystem::*;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource, MapEntitiesResource)]
 pub struct OrganismsLifecycle {
     // Organisms to be killed
-    deaths: Vec<Entity>,
+    deaths: Vec<(Entity, DeathCause)>,
     // Organisms to spawn
     pub births: Vec<OrganismBirth>,
 }
 impl OrganismsLifecycle {
-    pub fn add_death(&mut self, entity: Entity) {
-        if self.deaths.contains(&entity) {
+    pub fn add_death(&mut self, entity: Entity, cause: DeathCause) {
+        if self.deaths.iter().any(|(dead, _)| *dead == entity) {
             panic!("Already dead");
         }
-        self.deaths.push(entity);
+        self.deaths.push((entity, cause));
     }
 }
+// Pending deaths refer to entities that are renumbered when a save is loaded.
+impl MapEntities for OrganismsLifecycle {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        for (entity, _) in self.deaths.iter_mut() {
+            *entity = entity_mapper.map_entity(*entity);
+        }
+    }
+}
+// Why an organism died.
+#[derive(Reflect, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum DeathCause {
+    // Its body ran out of energy.
+    Starved,
+    // It was eaten by a mouth.
+    Eaten,
+    // It was replaced by the next generation.
+    Culled,
+}
+
+// What gave birth to an organism.
+#[derive(Reflect, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum BirthOrigin {
+    // Random organism added to reach the species minimum population.
+    MinimumPopulation,
+    AutoSpawn,
+    GenerationEvolution,
+    Birth,
+    // Organism imported from another simulation.
+    Import,
+}
+
+#[derive(Reflect)]
 pub struct OrganismBirth {
     // Species for the new organism
     pub species: SpeciesId,
@@ -26,6 +61,9 @@ pub struct OrganismBirth {
     pub energy: Option<f32>,
     // Chromosome for the new organism
     pub chromosome: Chromosome,
+    pub origin: BirthOrigin,
+    // Organisms the new organism is born from.
+    pub parents: Vec<OrganismId>,
 }
 
 //pub fn organism_death(world: &mut World) {
@@ -38,24 +76,37 @@ pub struct OrganismBirth {
 //        world.entity_mut(entity).despawn_recursive();
 //    }
 //}
+#[allow(clippy::too_many_arguments)]
 pub fn organism_lifecycle(
     mut commands: Commands,
     mut lifecycle: ResMut<OrganismsLifecycle>,
     ecosystem_config: Res<EcosystemConfig>,
     mut ecosystem: ResMut<EcosystemRuntime>,
     mut rng: ResMut<GlobalEntropy<WyRand>>,
+    gene_pools: Res<GenePools>,
+    mut statistics_flows: ResMut<StatisticsFlows>,
+    mut lineage_log: ResMut<LineageLog>,
     organisms: Query<&Organism>,
 ) {
-    for &entity in std::mem::take(&mut lifecycle.deaths).iter() {
+    lineage_log.start(ecosystem.steps, &ecosystem_config, organisms.iter());
+    for &(entity, cause) in std::mem::take(&mut lifecycle.deaths).iter() {
         let organism = organisms.get(entity).expect("Death of a non-Organism");
         ecosystem.decrease_population(&organism.species);
+        statistics_flows.get_mut(organism.species).add_death(cause);
+        lineage_log.write(&LineageEvent::Death {
+            step: ecosystem.steps,
+            id: organism.id,
+            cause,
+        });
         commands.entity(entity).despawn_recursive();
     }
     for OrganismBirth {
         species,
         position,
         energy,
-        chromosome,
+        mut chromosome,
+        origin,
+        parents,
     } in std::mem::take(&mut lifecycle.births).into_iter()
     {
         let position =
@@ -68,10 +119,16 @@ pub fn organism_lifecycle(
         let with_brain = config.eye.is_some();
         if with_brain {
             let individual = if chromosome.is_empty() {
-                OrganismIndividual::random(&mut *rng, config)
+                match gene_pools.sample(&mut *rng, config) {
+                    Some(chromosome) => OrganismIndividual::create(chromosome),
+                    None => OrganismIndividual::random(&mut *rng, config),
+                }
             } else {
-                OrganismIndividual::create(chromosome.clone())
+                config.chromosome_layout.repair(&mut chromosome);
+                OrganismIndividual::create(chromosome)
             };
+            // Keep the chromosome of random organisms, so that they can reproduce.
+            chromosome = individual.chromosome().clone();
             let (mut body, eye, locomotion, brain) = individual.into_components(config);
             if let Some(energy) = &energy {
                 body.energy = *energy;
@@ -99,7 +156,17 @@ pub fn organism_lifecycle(
         if let Some(uterus_config) = &config.uterus {
             command.insert(Uterus::new(uterus_config));
         }
-        command.insert(Organism::new(config.id, chromosome));
+        let id = ecosystem.new_organism_id();
+        lineage_log.write(&LineageEvent::Birth {
+            step: ecosystem.steps,
+            id,
+            species: config.name.clone(),
+            origin,
+            parents: parents.clone(),
+        });
+        command.insert(Organism::new(id, config.id, chromosome, parents));
         ecosystem.increase_population(&config.id);
+        statistics_flows.get_mut(config.id).add_birth(origin);
     }
+    lineage_log.flush();
 }
@@ -9,23 +9,55 @@ pub use reproduction::*;
 
 use crate::ecosystem::*;
 
+// Identifier of an organism, unique within an ecosystem and never reused.
+#[derive(
+    Reflect,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[reflect_value(Hash, PartialEq, Serialize, Deserialize)]
+pub struct OrganismId(pub u64);
+
 #[derive(Component, Reflect, Default, Debug)]
 #[reflect(Component)]
 pub struct Organism {
+    pub id: OrganismId,
     // Identifier of the species of this organism
     pub species: SpeciesId,
+    // Organisms this one was born from, the mother first for a Birth reproduction.
+    // Empty for organisms spawned without parents.
+    pub parents: Vec<OrganismId>,
     // Chromosome used to create this organism
     // This can be an empty Chromosome for species without reproduction.
     pub chromosome: Chromosome,
     // Time in ticks since spawning of this organism
     pub age: u32,
+    // Number of children this organism gave birth to
+    pub offspring: u32,
 }
 impl Organism {
-    pub fn new(species: SpeciesId, chromosome: Chromosome) -> Self {
+    pub fn new(
+        id: OrganismId,
+        species: SpeciesId,
+        chromosome: Chromosome,
+        parents: Vec<OrganismId>,
+    ) -> Self {
         Self {
+            id,
             species,
+            parents,
             chromosome,
             age: 0,
+            offspring: 0,
         }
     }
     pub fn tick(&mut self) {
@@ -22,6 +22,11 @@ impl Body {
             energy: config.starting_energy,
         }
     }
+    // Takes the new config values, the current energy is kept.
+    pub fn update_config(&mut self, config: &BodyConfig) {
+        self.config_maximum_energy = config.maximum_energy;
+        self.config_body_cost = config.body_cost;
+    }
     // Return current energy percentage in regard to maximum energy
     pub fn energy_pct(&self) -> f32 {
         self.energy / self.config_maximum_energy
@@ -38,17 +43,23 @@ impl Sensor for Body {
 }
 
 pub fn body_processing(
-    mut bodies: Query<(Entity, &mut Body)>,
+    mut bodies: Query<(Entity, &Organism, &mut Body)>,
     mut organisms_lifecycle: ResMut<OrganismsLifecycle>,
+    mut statistics_flows: ResMut<StatisticsFlows>,
     actors: Query<All<&dyn EnergyActor>>,
 ) {
-    for (entity, mut body) in bodies.iter_mut() {
+    for (entity, organism, mut body) in bodies.iter_mut() {
+        let flows = statistics_flows.get_mut(organism.species);
         // Update Body energy, based on consumers and producers
         let tick_energy = if let Ok(energy_actors) = actors.get(entity) {
             // Aggregate all energy produced and consumed this tick by this entity
             energy_actors
                 .into_iter()
-                .map(|actor| actor.energy_produced() - actor.energy_consumed())
+                .map(|actor| {
+                    let (produced, consumed) = (actor.energy_produced(), actor.energy_consumed());
+                    flows.add_energy(actor.organ(), produced, consumed);
+                    produced - consumed
+                })
                 .sum()
         } else {
             // Some organism can only have a Body, like plants with all leaves lost.
@@ -59,9 +70,11 @@ pub fn body_processing(
         body.energy =
             (body.energy + tick_energy - body.config_body_cost).min(body.config_maximum_energy);
 
+        flows.energy_spent_body += body.config_body_cost;
+
         // We die if we have no more energy.
         if body.energy <= 0.0 {
-            organisms_lifecycle.add_death(entity);
+            organisms_lifecycle.add_death(entity, DeathCause::Starved);
         }
     }
 }
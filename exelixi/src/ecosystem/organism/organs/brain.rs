@@ -7,69 +7,252 @@ use super::traits::Sensor;
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct Brain {
-    pub nn: nn::Network,
+    pub nn: BrainNetwork,
+    // Outputs of the recurrent layers at the previous step.
+    pub memory: Vec<Vec<f32>>,
+    energy_cost: f32,
+}
+
+// Neural network of a brain, depending on the configured BrainKind.
+#[derive(Reflect)]
+pub enum BrainNetwork {
+    Layered(nn::Network),
+    Neat(nn::GraphNetwork),
+}
+impl Default for BrainNetwork {
+    fn default() -> Self {
+        BrainNetwork::Layered(nn::Network::default())
+    }
+}
+impl BrainNetwork {
+    pub fn n_active_connections(&self) -> usize {
+        match self {
+            BrainNetwork::Layered(network) => network.n_active_connections(),
+            BrainNetwork::Neat(network) => network.n_active_connections(),
+        }
+    }
+    pub fn is_recurrent(&self) -> bool {
+        match self {
+            BrainNetwork::Layered(network) => network.is_recurrent(),
+            BrainNetwork::Neat(_) => false,
+        }
+    }
 }
 
 impl Brain {
-    pub fn random(rng: &mut dyn RngCore, n_sensors: usize, n_actuators: usize) -> Self {
-        Self {
-            nn: nn::Network::random(rng, &Self::topology(n_sensors, n_actuators)),
+    pub fn random(
+        rng: &mut dyn RngCore,
+        config: &BrainConfig,
+        n_sensors: usize,
+        n_actuators: usize,
+    ) -> Self {
+        match config.kind {
+            BrainKind::Layered => Self::new(
+                BrainNetwork::Layered(nn::Network::random(
+                    rng,
+                    &Self::topology(config, n_sensors, n_actuators),
+                )),
+                config,
+            ),
+            BrainKind::Neat { .. } => Self::from_neat_genome(
+                &ga::NeatGenome::minimal(rng, n_sensors, n_actuators),
+                config,
+            ),
         }
     }
 
+    // Layered brains use the genes, Neat brains use the NEAT genome of the chromosome.
     pub fn from_genes(
         genes: impl IntoIterator<Item = f32>,
+        neat: Option<&ga::NeatGenome>,
+        config: &BrainConfig,
         n_sensors: usize,
         n_actuators: usize,
     ) -> Self {
+        match config.kind {
+            BrainKind::Layered => Self::new(
+                BrainNetwork::Layered(nn::Network::from_genes(
+                    &Self::topology(config, n_sensors, n_actuators),
+                    genes,
+                )),
+                config,
+            ),
+            BrainKind::Neat { .. } => {
+                Self::from_neat_genome(neat.expect("Missing NEAT genome in the chromosome"), config)
+            }
+        }
+    }
+    fn from_neat_genome(genome: &ga::NeatGenome, config: &BrainConfig) -> Self {
+        let nodes = genome
+            .nodes
+            .iter()
+            .map(|node| (node.id, node.bias))
+            .collect::<Vec<_>>();
+        let connections = genome
+            .connections
+            .iter()
+            .map(|c| nn::GraphConnection {
+                from: c.from,
+                to: c.to,
+                weight: c.weight,
+                enabled: c.enabled,
+            })
+            .collect();
+        Self::new(
+            BrainNetwork::Neat(nn::GraphNetwork::new(
+                genome.n_inputs,
+                genome.n_outputs,
+                &nodes,
+                connections,
+                config.hidden_activation,
+                config.output_activation,
+            )),
+            config,
+        )
+    }
+    fn new(nn: BrainNetwork, config: &BrainConfig) -> Self {
+        let energy_cost = nn.n_active_connections() as f32 * config.connection_cost;
+        let memory = match &nn {
+            BrainNetwork::Layered(network) => network.empty_memory(),
+            BrainNetwork::Neat(_) => vec![],
+        };
         Self {
-            nn: nn::Network::from_weights(&Self::topology(n_sensors, n_actuators), genes),
+            nn,
+            memory,
+            energy_cost,
+        }
+    }
+    pub fn propagate(&mut self, inputs: &[f32]) -> Vec<f32> {
+        match &self.nn {
+            BrainNetwork::Layered(network) => {
+                network.propagate_with_memory(inputs, &mut self.memory)
+            }
+            BrainNetwork::Neat(network) => network.propagate(inputs),
         }
     }
     pub fn as_chromosome(&self) -> ga::Chromosome {
-        self.nn.weights().collect()
+        match &self.nn {
+            BrainNetwork::Layered(network) => network.genes().collect(),
+            BrainNetwork::Neat(network) => {
+                let nodes = network
+                    .nodes()
+                    .map(|(id, bias)| ga::NodeGene { id, bias })
+                    .collect();
+                let connections = network
+                    .connections()
+                    .iter()
+                    .map(|c| ga::ConnectionGene {
+                        innovation: ga::NeatGenome::innovation(c.from, c.to),
+                        from: c.from,
+                        to: c.to,
+                        weight: c.weight,
+                        enabled: c.enabled,
+                    })
+                    .collect();
+                ga::Chromosome::default().with_neat(ga::NeatGenome::new(
+                    network.n_inputs(),
+                    network.n_outputs(),
+                    nodes,
+                    connections,
+                ))
+            }
+        }
+    }
+    // Indices, in the brain chromosome, of the weight and enabled genes of connections between neurons.
+    // Neat brains have no such genes, their connections are in the NEAT genome.
+    pub fn connection_loci(
+        config: &BrainConfig,
+        n_sensors: usize,
+        n_actuators: usize,
+    ) -> Vec<ga::ConnectionLoci> {
+        match config.kind {
+            BrainKind::Layered => {
+                nn::Network::connection_loci(&Self::topology(config, n_sensors, n_actuators))
+                    .into_iter()
+                    .map(|(weight, enabled)| ga::ConnectionLoci::new(weight, enabled))
+                    .collect()
+            }
+            BrainKind::Neat { .. } => vec![],
+        }
+    }
+    // Description of the brain genes, layers are numbered from the first hidden layer.
+    // Neat brains have no genes, their network is in the NEAT genome.
+    pub fn layout(config: &BrainConfig, n_sensors: usize, n_actuators: usize) -> Vec<ga::Locus> {
+        match config.kind {
+            BrainKind::Layered => {
+                let limit = config.max_weight.unwrap_or(f32::INFINITY);
+                nn::Network::gene_loci(&Self::topology(config, n_sensors, n_actuators))
+                    .into_iter()
+                    .map(|(layer, neuron, gene)| {
+                        let name = format!("brain.l{layer}.n{neuron}");
+                        match gene {
+                            nn::NeuronGene::Bias => {
+                                ga::Locus::continuous(format!("{name}.bias"), -limit, limit)
+                            }
+                            nn::NeuronGene::Weight(input) => {
+                                ga::Locus::continuous(format!("{name}.w{input}"), -limit, limit)
+                            }
+                            nn::NeuronGene::Enabled(input) => {
+                                ga::Locus::boolean(format!("{name}.e{input}"))
+                            }
+                        }
+                    })
+                    .collect()
+            }
+            BrainKind::Neat { .. } => vec![],
+        }
     }
 
-    fn topology(n_sensors: usize, n_actuators: usize) -> [nn::LayerTopology; 3] {
-        //let mut n_sensors = body.n_sensors();
-        //if let Some(eye) = eye {
-        //    n_sensors += eye.n_sensors();
-        //}
-        //let mut n_actuators = 0;
-        //if let Some(locomotion) = locomotion {
-        //    n_actuators += locomotion.n_actuators();
-        //}
-        [
-            nn::LayerTopology { neurons: n_sensors },
-            nn::LayerTopology {
-                neurons: 2 * n_sensors,
-            },
-            nn::LayerTopology {
-                neurons: n_actuators,
-            },
-        ]
+    // Input layer of n_sensors neurons, then the config hidden layers, then output layer of n_actuators neurons.
+    fn topology(
+        config: &BrainConfig,
+        n_sensors: usize,
+        n_actuators: usize,
+    ) -> Vec<nn::LayerTopology> {
+        std::iter::once((n_sensors, nn::Activation::Identity, false))
+            .chain(config.hidden_layers.iter().enumerate().map(|(i, size)| {
+                (
+                    size.n_neurons(n_sensors, n_actuators),
+                    config.hidden_activation,
+                    config.recurrent_layers.contains(&i),
+                )
+            }))
+            .chain(std::iter::once((
+                n_actuators,
+                config.output_activation,
+                false,
+            )))
+            .map(|(neurons, activation, recurrent)| nn::LayerTopology {
+                neurons,
+                activation,
+                recurrent,
+            })
+            .collect()
     }
 }
 
 impl EnergyActor for Brain {
-    // Currently brain has no cost.
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Brain
+    }
+    // The brain cost is proportional to its number of enabled connections.
     fn energy_consumed(&self) -> f32 {
-        0.0
+        self.energy_cost
     }
 }
 
 pub fn brain_processing(
-    brains: Query<(Entity, &Brain)>,
+    mut brains: Query<(Entity, &mut Brain)>,
     sensors: Query<(&Body, &Eye)>,
     mut actuators: Query<&mut Locomotion>,
 ) {
-    for (entity, brain) in brains.iter() {
+    for (entity, mut brain) in brains.iter_mut() {
         let nn_inputs = if let Ok((body, eye)) = sensors.get(entity) {
             [eye.sensors(), body.sensors()].concat()
         } else {
             vec![]
         };
-        let mut nn_output = brain.nn.propagate(&nn_inputs).into_iter();
+        let mut nn_output = brain.propagate(&nn_inputs).into_iter();
         if let Ok(mut locomotion) = actuators.get_mut(entity) {
             locomotion.actuates(&mut nn_output);
         }
@@ -80,3 +263,77 @@ pub fn brain_processing(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_layers() {
+        let config = BrainConfig {
+            hidden_layers: vec![
+                LayerSize::Sensors(1.5),
+                LayerSize::Neurons(3),
+                LayerSize::Actuators(2.0),
+            ],
+            connection_cost: 0.5,
+            ..Default::default()
+        };
+        // 4 sensors and 2 actuators, with hidden layers of 6, 3 and 4 neurons.
+        // Each neuron has a bias, then a weight and an enabled flag per input.
+        let layout = Brain::layout(&config, 4, 2);
+        let n_genes = 6 * (1 + 2 * 4) + 3 * (1 + 2 * 6) + 4 * (1 + 2 * 3) + 2 * (1 + 2 * 4);
+        assert_eq!(layout.len(), n_genes);
+        assert_eq!(layout[0].name, "brain.l0.n0.bias");
+        assert_eq!(layout[4].name, "brain.l0.n0.w3");
+        assert_eq!(layout[5].name, "brain.l0.n0.e0");
+        assert_eq!(layout[n_genes - 1].name, "brain.l3.n1.e3");
+        // All connections enabled, except the first one of each neuron.
+        let genes = layout
+            .iter()
+            .enumerate()
+            .map(|(i, locus)| match locus.kind {
+                ga::GeneKind::Boolean if locus.name.ends_with(".e0") => 0.0,
+                ga::GeneKind::Boolean => 1.0,
+                _ => 0.01 * i as f32,
+            })
+            .collect::<Vec<_>>();
+        let mut brain = Brain::from_genes(genes.clone(), None, &config, 4, 2);
+        assert_eq!(
+            brain.nn.n_active_connections(),
+            6 * 3 + 3 * 5 + 4 * 2 + 2 * 3
+        );
+        assert_eq!(brain.energy_consumed(), 23.5);
+        assert_eq!(brain.propagate(&[1.0; 4]).len(), 2);
+        // Disabled connections keep their weight.
+        assert_eq!(
+            brain.as_chromosome().iter().copied().collect::<Vec<_>>(),
+            genes
+        );
+    }
+
+    #[test]
+    fn recurrent_memory() {
+        let config = BrainConfig {
+            hidden_layers: vec![LayerSize::Neurons(2)],
+            hidden_activation: nn::Activation::Identity,
+            output_activation: nn::Activation::Identity,
+            recurrent_layers: vec![0],
+            ..Default::default()
+        };
+        // 1 sensor and 1 actuator, the recurrent layer neurons have the 2 layer outputs as extra inputs.
+        let layout = Brain::layout(&config, 1, 1);
+        assert_eq!(layout.len(), 2 * (1 + 2 * (1 + 2)) + (1 + 2 * 2));
+        let genes = layout.iter().map(|locus| match locus.kind {
+            ga::GeneKind::Boolean => 1.0,
+            _ => 0.1,
+        });
+        let mut brain = Brain::from_genes(genes, None, &config, 1, 1);
+        assert!(brain.nn.is_recurrent());
+        assert_eq!(brain.memory, vec![vec![0.0, 0.0], vec![]]);
+        let first = brain.propagate(&[1.0]);
+        let second = brain.propagate(&[1.0]);
+        assert_ne!(first, second);
+        assert!(brain.memory[0].iter().all(|value| *value > 0.2));
+    }
+}
@@ -50,29 +50,24 @@ impl Eye {
             ],
         }
     }
+    // Genes are expected to be within the chromosome layout bounds.
     pub fn from_genes(genes: impl IntoIterator<Item = f32>, config: &EyeConfig) -> Self {
         let mut genes = genes.into_iter();
         let fov_angle = match config.fov_angle {
             ConfigValue::Fixed(v) => v,
-            ConfigValue::Gene { min, max } => {
-                let gene = genes.next().expect("Missing gene for the fov_angle");
-                gene.clamp(min, max)
-            }
+            ConfigValue::Gene { .. } => genes.next().expect("Missing gene for the fov_angle"),
             _ => panic!(),
         };
         let fov_range = match config.fov_range {
             ConfigValue::Fixed(v) => v,
-            ConfigValue::Gene { min, max } => {
-                let gene = genes.next().expect("Missing gene for the fov_range");
-                gene.clamp(min, max)
-            }
+            ConfigValue::Gene { .. } => genes.next().expect("Missing gene for the fov_range"),
             _ => panic!(),
         };
         let (_n_sectors, n_cells) = match config.n_cells {
             ConfigValue::Fixed(v) => (v, v),
-            ConfigValue::Gene { min, max } => {
+            ConfigValue::Gene { max, .. } => {
                 let gene = genes.next().expect("Missing gene for the n_eye_cells");
-                ((gene as u8).clamp(min, max), max)
+                (gene as u8, max)
             }
             _ => panic!(),
         };
@@ -162,6 +157,14 @@ impl Eye {
             .collect::<Vec<_>>();
     }
 
+    // Takes the new config values, the genes decoded values are kept.
+    // The number of sensors must not change, as the brain depends on it.
+    pub fn update_config(&mut self, config: &EyeConfig) {
+        self.visible = config.visible_species.clone();
+        self.cell_sensors = config.cell_sensors;
+        self.energy_cost =
+            Eye::compute_energy_cost(self.fov_range, self.fov_angle, config.energy_cost);
+    }
     // Return the number of sensors per cell of this eye
     pub fn n_cell_sensors(&self) -> usize {
         self.cell_sensors.n_sensors(self.visible.len())
@@ -171,6 +174,9 @@ impl Eye {
     }
 }
 impl EnergyActor for Eye {
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Eye
+    }
     fn energy_consumed(&self) -> f32 {
         self.energy_cost
     }
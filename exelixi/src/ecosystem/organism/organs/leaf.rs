@@ -19,8 +19,15 @@ impl Leaf {
             energy_production: config.energy_production,
         }
     }
+    // Takes the new config values, the remaining lifetime is kept.
+    pub fn update_config(&mut self, config: &LeafConfig) {
+        self.energy_production = config.energy_production;
+    }
 }
 impl EnergyActor for Leaf {
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Leaf
+    }
     fn energy_produced(&self) -> f32 {
         self.energy_production
     }
@@ -16,6 +16,8 @@ pub struct Locomotion {
     pub linear_max: f32,
     linear_cost: f32,
     angular_cost: f32,
+    // Total distance travelled since birth.
+    pub distance: f32,
 }
 
 impl Locomotion {
@@ -28,6 +30,7 @@ impl Locomotion {
                 linear_max: v,
                 linear_cost: config.linear_cost,
                 angular_cost: config.angular_cost,
+                distance: 0.0,
             },
             ConfigValue::Neuron { min: _, max } => Self {
                 linear: 0.0,
@@ -36,12 +39,29 @@ impl Locomotion {
                 linear_max: max,
                 linear_cost: config.linear_cost,
                 angular_cost: config.angular_cost,
+                distance: 0.0,
             },
             _ => panic!(),
         }
     }
+    // The kind of linear value must not change, as the brain actuators depend on it.
+    pub fn update_config(&mut self, config: &LocomotionConfig) {
+        match config.linear {
+            ConfigValue::Fixed(v) if !self.linear_actuator => {
+                self.linear = v;
+                self.linear_max = v;
+            }
+            ConfigValue::Neuron { min: _, max } if self.linear_actuator => self.linear_max = max,
+            _ => (),
+        }
+        self.linear_cost = config.linear_cost;
+        self.angular_cost = config.angular_cost;
+    }
 }
 impl EnergyActor for Locomotion {
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Locomotion
+    }
     fn energy_consumed(&self) -> f32 {
         self.linear_cost * self.linear.powi(2) + self.angular_cost * self.angular.powi(2)
     }
@@ -69,17 +89,18 @@ impl Actuator for Locomotion {
 }
 
 pub fn locomotion_processing(
-    mut movables: Query<(&mut Position, &Locomotion)>,
+    mut movables: Query<(&mut Position, &mut Locomotion)>,
     config: Res<EcosystemConfig>,
 ) {
     let half_width = config.environment.width as f32 / 2.0;
     let half_height = config.environment.height as f32 / 2.0;
-    for (mut position, locomotion) in movables.iter_mut() {
+    for (mut position, mut locomotion) in movables.iter_mut() {
         // Update transform based on linear and angular velocity
         let delta_x = position.angle().cos() * locomotion.linear;
         let delta_y = position.angle().sin() * locomotion.linear;
         position.x += delta_x;
         position.y += delta_y;
+        locomotion.distance += locomotion.linear.abs();
         let new_angle = position.angle() + locomotion.angular;
         position.set_angle(new_angle);
         if config.environment.wall {
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 
 use crate::ecosystem::*;
 
@@ -11,6 +11,8 @@ pub struct Mouth {
     pub edible: Vec<SpeciesId>,
     // Total energy eaten from food this tick.
     pub energy_eaten: f32,
+    // Total energy eaten from food since birth.
+    pub energy_eaten_total: f32,
 }
 
 impl Mouth {
@@ -19,10 +21,18 @@ impl Mouth {
             reach: config.reach,
             edible: config.edible_species.clone(),
             energy_eaten: 0.0,
+            energy_eaten_total: 0.0,
         }
     }
+    pub fn update_config(&mut self, config: &MouthConfig) {
+        self.reach = config.reach;
+        self.edible = config.edible_species.clone();
+    }
 }
 impl EnergyActor for Mouth {
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Mouth
+    }
     fn energy_produced(&self) -> f32 {
         self.energy_eaten
     }
@@ -39,7 +49,10 @@ pub fn mouth_processing(
     // Store for each eatable organisms, the list of each organism that want to eat it
     // with the distance it is at.
     // Only the closest will be able to eat it.
-    let mut foods = BTreeMap::new();
+    // Foods are kept in the order they are first reached, and not by Entity,
+    // as entities are renumbered when a save is loaded.
+    let mut foods: Vec<(Entity, Vec<(f32, Entity)>)> = vec![];
+    let mut food_indices = HashMap::new();
     for (entity, position, mut mouth) in eaters.iter_mut() {
         mouth.energy_eaten = 0.0;
         for species in mouth.edible.iter() {
@@ -57,10 +70,11 @@ pub fn mouth_processing(
                 }
             }
             if let Some((food_entity, food_distance)) = food {
-                foods
-                    .entry(food_entity)
-                    .or_insert(vec![])
-                    .push((food_distance, entity));
+                let index = *food_indices.entry(food_entity).or_insert_with(|| {
+                    foods.push((food_entity, vec![]));
+                    foods.len() - 1
+                });
+                foods[index].1.push((food_distance, entity));
             }
         }
     }
@@ -68,7 +82,9 @@ pub fn mouth_processing(
     // We store the energy of each eaten organism before applying any mouth eating
     // so that eaten energy is independent of order of mouth eating.
     let food_energy = HashMap::<Entity, f32>::from_iter(
-        foods.keys().map(|e| (*e, bodies.get(*e).unwrap().energy)),
+        foods
+            .iter()
+            .map(|(e, _)| (*e, bodies.get(*e).unwrap().energy)),
     );
     for (food_entity, mut food_eaters) in foods.into_iter() {
         food_eaters.sort_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap());
@@ -78,8 +94,9 @@ pub fn mouth_processing(
         {
             let (_, _, mut mouth) = eaters.get_mut(e).unwrap();
             mouth.energy_eaten += food_energy[&food_entity];
+            mouth.energy_eaten_total += food_energy[&food_entity];
             has_eaten.insert(e);
-            organisms_lifecycle.add_death(food_entity);
+            organisms_lifecycle.add_death(food_entity, DeathCause::Eaten);
         }
     }
 }
@@ -1,5 +1,18 @@
+// Organs that produce or consume energy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnergyOrgan {
+    Brain,
+    Eye,
+    Leaf,
+    Locomotion,
+    Mouth,
+    Uterus,
+}
+
 #[bevy_trait_query::queryable]
 pub trait EnergyActor {
+    // Organ this actor is, for the energy statistics.
+    fn organ(&self) -> EnergyOrgan;
     // Return the energy consumed in this tick.
     fn energy_consumed(&self) -> f32 {
         0.0
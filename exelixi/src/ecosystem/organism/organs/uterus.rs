@@ -12,16 +12,25 @@ pub struct Uterus {
     // has mated with.
     // None while we have not yet mated, or we have given birth.
     pub chromosome: Option<Chromosome>,
+    // The organism the chromosome comes from.
+    pub mate: Option<OrganismId>,
 }
 impl Uterus {
     pub fn new(config: &UterusConfig) -> Self {
         Self {
             mating_distance: config.mating_distance,
             chromosome: None,
+            mate: None,
         }
     }
+    pub fn update_config(&mut self, config: &UterusConfig) {
+        self.mating_distance = config.mating_distance;
+    }
 }
 impl EnergyActor for Uterus {
+    fn organ(&self) -> EnergyOrgan {
+        EnergyOrgan::Uterus
+    }
     fn energy_consumed(&self) -> f32 {
         0.0
     }
@@ -44,6 +53,7 @@ pub fn uterus_processing(
         }) {
             let mate = organisms.get(nearest.item.entity).unwrap();
             uterus.chromosome = Some(mate.chromosome.clone());
+            uterus.mate = Some(mate.id);
         }
     }
 }
@@ -55,6 +55,8 @@ pub fn auto_spawning(
                     position: pos,
                     energy: None,
                     chromosome: Chromosome::default(),
+                    origin: BirthOrigin::AutoSpawn,
+                    parents: vec![],
                 });
             }
         }
@@ -3,12 +3,12 @@ use lib_genetic_algorithm::{CrossoverMethod, MutationMethod};
 use crate::ecosystem::*;
 
 pub fn reproduction_birth(
-    mut organisms: Query<(&Organism, &Position, &mut Body, &mut Uterus)>,
+    mut organisms: Query<(&mut Organism, &Position, &mut Body, &mut Uterus)>,
     ecosystem_config: Res<EcosystemConfig>,
     mut rng: ResMut<GlobalEntropy<WyRand>>,
     mut organisms_lifecycle: ResMut<OrganismsLifecycle>,
 ) {
-    for (organism, position, mut body, mut uterus) in organisms.iter_mut() {
+    for (mut organism, position, mut body, mut uterus) in organisms.iter_mut() {
         let config = &ecosystem_config.species[&organism.species];
         if let ReproductionConfig::Birth {
             minimum_age,
@@ -16,28 +16,39 @@ pub fn reproduction_birth(
             child_energy_pct,
             mutation_chance,
             mutation_amplitude,
+            ref crossover,
+            ref mutation,
         } = config.reproduction
         {
             if organism.age >= minimum_age && body.energy_pct() >= minimum_energy_pct {
                 if let Some(other_chromosome) = uterus.chromosome.take() {
-                    let crossover_method = ga::UniformCrossover;
+                    let parents = std::iter::once(organism.id)
+                        .chain(uterus.mate.take())
+                        .collect();
+                    let crossover_method = crossover_method(config, crossover);
                     let mutation_method =
-                        ga::GaussianMutation::new(mutation_chance, mutation_amplitude);
+                        mutation_method(config, mutation, mutation_chance, mutation_amplitude);
+                    // The mother is taken as the fitter parent: unlike the mate,
+                    // she reached the age and energy needed to give birth.
                     let mut child_chromosome = crossover_method.crossover(
                         &mut *rng,
                         &organism.chromosome,
                         &other_chromosome,
                     );
                     mutation_method.mutate(&mut *rng, &mut child_chromosome);
+                    config.chromosome_layout.repair(&mut child_chromosome);
                     let child_energy = body.energy * child_energy_pct;
                     let child_position = (*position).with_random_angle(&mut *rng);
                     let parent_energy = body.energy - child_energy;
                     body.energy = parent_energy;
+                    organism.offspring += 1;
                     organisms_lifecycle.births.push(OrganismBirth {
                         species: organism.species,
                         position: Some(child_position),
                         energy: Some(child_energy),
                         chromosome: child_chromosome,
+                        origin: BirthOrigin::Birth,
+                        parents,
                     });
                 }
             }
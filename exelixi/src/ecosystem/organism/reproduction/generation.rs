@@ -2,45 +2,81 @@ use lib_genetic_algorithm::Individual;
 
 use crate::ecosystem::{organism::reproduction::individual::OrganismIndividual, *};
 
+use std::collections::BTreeMap;
 use std::f32::consts::PI;
 
+type EvolvedOrganism<'a> = (
+    Entity,
+    &'a Organism,
+    &'a Position,
+    &'a Body,
+    Option<&'a Mouth>,
+    Option<&'a Locomotion>,
+);
+
 #[allow(clippy::too_many_arguments)]
 pub fn evolve(
     config: Res<EcosystemConfig>,
-    organisms: Query<(Entity, &Organism, &Position, &Body, &Brain, Option<&Eye>)>,
+    organisms: Query<
+        (
+            Entity,
+            &Organism,
+            &Position,
+            &Body,
+            Option<&Mouth>,
+            Option<&Locomotion>,
+        ),
+        With<Brain>,
+    >,
+    organs: Query<(Option<&Eye>, &Brain)>,
     mut ecosystem: ResMut<EcosystemRuntime>,
     mut rng: ResMut<GlobalEntropy<WyRand>>,
     mut generation_evolutions: ResMut<GenerationEvolutions>,
+    mut novelty_archives: ResMut<NoveltyArchives>,
     mut organisms_lifecycle: ResMut<OrganismsLifecycle>,
 ) {
     for (species, state) in generation_evolutions.per_species.iter_mut() {
-        if ecosystem.steps % state.generation_length == 0 {
+        if ecosystem.steps.is_multiple_of(state.generation_length) {
             let minimum_population = config.species[species].minimum_population;
-            let mut organisms = organisms.iter().collect::<Vec<_>>();
+            let mut organisms = organisms
+                .iter()
+                .filter(|(_, organism, _, _, _, _)| &organism.species == species)
+                .collect::<Vec<_>>();
             organisms.sort_unstable_by(|(_, _, p1, _, _, _), (_, _, p2, _, _, _)| {
                 p1.partial_cmp(p2).unwrap()
             });
             let total_energy = organisms
                 .iter()
-                .filter(|(_, organism, _, _, _, _)| &organism.species == species)
                 .map(|(_, _, _, b, _, _)| b.energy)
                 .sum::<f32>();
             let current_positions = organisms
                 .iter()
-                .filter(|(_, organism, _, _, _, _)| &organism.species == species)
-                .map(|(_, _, p, _, _, _)| p)
+                .map(|(_, _, p, _, _, _)| *p)
                 .collect::<Vec<_>>();
+            let novelty_archive = novelty_archives.get_mut(&state.config, &state.fitness);
+            let fitnesses =
+                evaluate_fitness(state, novelty_archive, &organisms, &config, &mut *rng);
             let current_individuals = organisms
                 .iter()
-                .filter(|(_, organism, _, _, _, _)| &organism.species == species)
-                .map(|(entity, _, _, body, brain, eye)| {
-                    organisms_lifecycle.add_death(*entity);
-                    OrganismIndividual::from_components(&state.config, body, eye, brain)
+                .zip(fitnesses)
+                .map(|((entity, organism, _, body, _, _), fitness)| {
+                    organisms_lifecycle.add_death(*entity, DeathCause::Culled);
+                    // Organisms loaded from older saves can have no chromosome,
+                    // it is rebuilt from their organs.
+                    let chromosome = if organism.chromosome.is_empty() {
+                        let (eye, brain) = organs.get(*entity).expect("Organism without Brain");
+                        OrganismIndividual::from_components(&state.config, body, &eye, brain)
+                            .chromosome()
+                            .clone()
+                    } else {
+                        organism.chromosome.clone()
+                    };
+                    OrganismIndividual::new(fitness, chromosome)
                 })
                 .collect::<Vec<_>>();
             ecosystem.increment_generation(species);
 
-            let new_individuals = state.genetic_algorithm.evolve(
+            let new_individuals = state.genetic_algorithm.evolve_with_parents(
                 &mut *rng,
                 &current_individuals,
                 state.fertility_rate,
@@ -56,7 +92,7 @@ pub fn evolve(
             new_individuals
                 .into_iter()
                 .enumerate()
-                .for_each(|(i, individual)| {
+                .for_each(|(i, (individual, parents))| {
                     let position = match (current_positions.is_empty(), state.child_spawn_distance)
                     {
                         (false, Some(distance)) => {
@@ -77,6 +113,11 @@ pub fn evolve(
                         position: Some(position),
                         energy: Some(evolve_energy),
                         chromosome: individual.chromosome().clone(),
+                        origin: BirthOrigin::GenerationEvolution,
+                        parents: parents
+                            .into_iter()
+                            .map(|parent| organisms[parent].1.id)
+                            .collect(),
                     });
                 });
         }
@@ -84,3 +125,116 @@ pub fn evolve(
     // Increase steps at the end so that we can have a first evolution at step = 0
     ecosystem.steps += 1;
 }
+
+// Fitness of each organism from the configured objectives.
+// With several objectives, the fitness is computed from their Pareto ranking.
+fn evaluate_fitness(
+    state: &GenerationEvolution,
+    novelty_archive: &mut ga::NoveltyArchive,
+    organisms: &[EvolvedOrganism],
+    config: &EcosystemConfig,
+    rng: &mut dyn RngCore,
+) -> Vec<f32> {
+    let novelty = if state
+        .fitness
+        .objectives
+        .contains(&FitnessObjective::Novelty)
+    {
+        // The behaviour of an organism is its final position, relative to the environment size.
+        let behaviours = organisms
+            .iter()
+            .map(|(_, _, position, _, _, _)| {
+                vec![
+                    position.x / config.environment.width as f32,
+                    position.y / config.environment.height as f32,
+                ]
+            })
+            .collect::<Vec<_>>();
+        let novelty = novelty_archive.novelty(&behaviours);
+        novelty_archive.archive(rng, &behaviours);
+        novelty
+    } else {
+        vec![0.0; organisms.len()]
+    };
+    // Surviving children of each organism of the previous generation.
+    let mut children = BTreeMap::new();
+    for (_, organism, _, _, _, _) in organisms.iter() {
+        for parent in organism.parents.iter() {
+            *children.entry(*parent).or_insert(0u32) += 1;
+        }
+    }
+    let objectives = organisms
+        .iter()
+        .zip(novelty)
+        .map(|((_, organism, _, body, mouth, locomotion), novelty)| {
+            state
+                .fitness
+                .objectives
+                .iter()
+                .map(|objective| match objective {
+                    FitnessObjective::Energy => body.energy,
+                    FitnessObjective::Age => organism.age as f32,
+                    FitnessObjective::EnergyEaten => {
+                        mouth.map_or(0.0, |mouth| mouth.energy_eaten_total)
+                    }
+                    FitnessObjective::Distance => {
+                        locomotion.map_or(0.0, |locomotion| locomotion.distance)
+                    }
+                    FitnessObjective::Offspring => {
+                        let parents_children = organism
+                            .parents
+                            .iter()
+                            .map(|parent| children[parent] as f32)
+                            .sum::<f32>();
+                        organism.offspring as f32
+                            + parents_children / organism.parents.len().max(1) as f32
+                    }
+                    FitnessObjective::Novelty => novelty,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    if state.fitness.objectives.len() == 1 {
+        objectives.into_iter().map(|values| values[0]).collect()
+    } else {
+        ga::pareto_fitness(&objectives)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn offspring_objective() {
+        let mut user_config: UserEcosystemConfig =
+            ron::from_str(include_str!("../../../../../configs/default.ron")).unwrap();
+        if let ReproductionConfig::GenerationEvolution { fitness, .. } =
+            &mut user_config.species[0].reproduction
+        {
+            fitness.objectives = vec![FitnessObjective::Offspring];
+        }
+        let config = EcosystemConfig::from_user_config(user_config).unwrap();
+        let state = GenerationEvolution::new(&config.species[&SpeciesId::new(0)]);
+        let organism = |parents: &[u64]| {
+            Organism::new(
+                OrganismId::default(),
+                SpeciesId::new(0),
+                ga::Chromosome::default(),
+                parents.iter().copied().map(OrganismId).collect(),
+            )
+        };
+        // Organism 1 of the previous generation had 2 surviving children, organism 2 had 1.
+        let organisms = [organism(&[1, 2]), organism(&[1]), organism(&[])];
+        let (position, body) = (Position::default(), Body::default());
+        let evolved = organisms
+            .iter()
+            .map(|organism| (Entity::PLACEHOLDER, organism, &position, &body, None, None))
+            .collect::<Vec<_>>();
+        let mut novelty_archive = ga::NoveltyArchive::new(1, 0.0);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let fitnesses = evaluate_fitness(&state, &mut novelty_archive, &evolved, &config, &mut rng);
+        assert_eq!(fitnesses, vec![1.5, 2.0, 0.0]);
+    }
+}
@@ -3,7 +3,7 @@ use ga::Chromosome;
 use lib_genetic_algorithm as ga;
 
 pub struct OrganismIndividual {
-    pub energy: f32,
+    pub fitness: f32,
     chromosome: ga::Chromosome,
 }
 
@@ -13,15 +13,22 @@ impl ga::Individual for OrganismIndividual {
     }
     fn create(chromosome: ga::Chromosome) -> Self {
         Self {
-            energy: 0.0,
+            fitness: 0.0,
             chromosome,
         }
     }
     fn fitness(&self) -> f32 {
-        self.energy
+        self.fitness
     }
 }
 impl OrganismIndividual {
+    pub fn new(fitness: f32, chromosome: ga::Chromosome) -> Self {
+        Self {
+            fitness,
+            chromosome,
+        }
+    }
+    // The step size gene, that organs do not hold, is at its initial value.
     pub fn from_components(
         config: &SpeciesConfig,
         body: &Body,
@@ -34,10 +41,14 @@ impl OrganismIndividual {
         }
         let brain_chromosome = brain.as_chromosome();
         //println!("Size {} {}", eye_chromosome.len(), brain_chromosome.len());
-        chromosome.extend(brain_chromosome);
+        chromosome.append(brain_chromosome);
         //println!("  -> {}", eye_chromosome.len());
+        // The step size gene comes after all organ genes.
+        if let Some(step) = config.reproduction.self_adaptive_step() {
+            chromosome.extend([step]);
+        }
         Self {
-            energy: body.energy,
+            fitness: body.energy,
             chromosome,
         }
     }
@@ -45,7 +56,14 @@ impl OrganismIndividual {
         self,
         config: &SpeciesConfig,
     ) -> (Body, Option<Eye>, Option<Locomotion>, Brain) {
-        let mut genes = self.chromosome.into_iter();
+        let neat = self.chromosome.neat().cloned();
+        // Only the organ genes are decoded, not the mutation genes after them.
+        let n_organ_genes = config
+            .chromosome_layout
+            .iter()
+            .filter(|locus| locus.segment() != "mutation")
+            .count();
+        let mut genes = self.chromosome.into_iter().take(n_organ_genes);
         let body = Body::new(&config.body);
         let locomotion = config.locomotion.as_ref().map(Locomotion::new);
         let eye = config
@@ -60,7 +78,13 @@ impl OrganismIndividual {
         if let Some(locomotion) = &locomotion {
             n_actuators += locomotion.n_actuators();
         }
-        let brain = Brain::from_genes(&mut genes, n_sensors, n_actuators);
+        let brain = Brain::from_genes(
+            &mut genes,
+            neat.as_ref(),
+            &config.brain,
+            n_sensors,
+            n_actuators,
+        );
         (body, eye, locomotion, brain)
     }
     pub fn random(mut rng: &mut dyn RngCore, config: &SpeciesConfig) -> Self {
@@ -75,7 +99,165 @@ impl OrganismIndividual {
         if let Some(locomotion) = &locomotion {
             n_actuators += locomotion.n_actuators();
         }
-        let brain = Brain::random(&mut rng, n_sensors, n_actuators);
+        let brain = Brain::random(&mut rng, &config.brain, n_sensors, n_actuators);
         Self::from_components(config, &body, &eye.as_ref(), &brain)
     }
+    // Chromosome loci of the brain connections, that can be switched on or off by mutation.
+    pub fn brain_connection_loci(config: &SpeciesConfig) -> Vec<ga::ConnectionLoci> {
+        // Organisms without eye have no brain.
+        let Some(eye_config) = &config.eye else {
+            return vec![];
+        };
+        let (n_sensors, n_actuators) = Self::brain_io(config, eye_config);
+        // Brain genes come after the eye genes.
+        Brain::connection_loci(&config.brain, n_sensors, n_actuators)
+            .into_iter()
+            .map(|connection| {
+                ga::ConnectionLoci::new(
+                    connection.weight + eye_config.n_genes(),
+                    connection.enabled + eye_config.n_genes(),
+                )
+            })
+            .collect()
+    }
+    // Description of the chromosome genes: eye genes, then brain genes,
+    // then the step size gene of self-adaptive mutation.
+    pub fn chromosome_layout(config: &SpeciesConfig) -> ga::ChromosomeLayout {
+        // Organisms without eye have no brain, and an empty chromosome.
+        let Some(eye_config) = &config.eye else {
+            return ga::ChromosomeLayout::default();
+        };
+        let (n_sensors, n_actuators) = Self::brain_io(config, eye_config);
+        let mut loci = eye_config.layout();
+        loci.extend(Brain::layout(&config.brain, n_sensors, n_actuators));
+        if config.reproduction.self_adaptive_step().is_some() {
+            loci.push(ga::Locus::continuous(
+                "mutation.step",
+                ga::SelfAdaptiveMutation::MINIMUM_STEP,
+                f32::INFINITY,
+            ));
+        }
+        ga::ChromosomeLayout::new(loci)
+    }
+    // Checks that organisms of the species can be built from the chromosome.
+    // The layout of the species the chromosome comes from, when known, must have the same loci,
+    // so that genes are not decoded as other traits.
+    pub fn check_chromosome(
+        config: &SpeciesConfig,
+        chromosome: &Chromosome,
+        source_layout: Option<&ga::ChromosomeLayout>,
+    ) -> Result<(), String> {
+        let layout = &config.chromosome_layout;
+        if chromosome.len() != layout.len() {
+            return Err(format!(
+                "{} genes where species {} expects {}",
+                chromosome.len(),
+                config.name,
+                layout.len()
+            ));
+        }
+        if let Some(source_layout) = source_layout {
+            if source_layout.len() != layout.len() {
+                return Err(format!(
+                    "{} loci where species {} expects {}",
+                    source_layout.len(),
+                    config.name,
+                    layout.len()
+                ));
+            }
+            for (i, (source_locus, locus)) in source_layout.iter().zip(layout.iter()).enumerate() {
+                if source_locus.name != locus.name {
+                    return Err(format!(
+                        "gene {i} is {} where species {} expects {}",
+                        source_locus.name, config.name, locus.name
+                    ));
+                }
+            }
+        }
+        for (locus, gene) in layout.iter().zip(chromosome.iter()) {
+            if locus.repair(*gene) != *gene {
+                return Err(format!(
+                    "gene {} value {gene} is not a valid {:?} within [{}, {}]",
+                    locus.name, locus.kind, locus.min, locus.max
+                ));
+            }
+        }
+        if let Some(eye_config) = &config.eye {
+            let (n_sensors, n_actuators) = Self::brain_io(config, eye_config);
+            match (&config.brain.kind, chromosome.neat()) {
+                (BrainKind::Layered, None) => {}
+                (BrainKind::Neat { .. }, Some(neat)) => {
+                    if (neat.n_inputs, neat.n_outputs) != (n_sensors, n_actuators) {
+                        return Err(format!(
+                            "NEAT genome of {} inputs and {} outputs where species {} expects {n_sensors} and {n_actuators}",
+                            neat.n_inputs, neat.n_outputs, config.name
+                        ));
+                    }
+                }
+                (kind, _) => {
+                    return Err(format!(
+                        "brain genome does not match species {} brain kind {kind:?}",
+                        config.name
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+    // Number of sensors and actuators the brain of the species is connected to.
+    fn brain_io(config: &SpeciesConfig, eye_config: &EyeConfig) -> (usize, usize) {
+        let n_sensors = Body::new(&config.body).n_sensors() + eye_config.n_sensors();
+        let n_actuators = config
+            .locomotion
+            .as_ref()
+            .map(|cfg| Locomotion::new(cfg).n_actuators())
+            .unwrap_or(0);
+        (n_sensors, n_actuators)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ga::Individual;
+    use rand::SeedableRng;
+
+    #[test]
+    fn self_adaptive_chromosome() {
+        let mut user_config: UserEcosystemConfig =
+            ron::from_str(include_str!("../../../../../configs/default.ron")).unwrap();
+        if let ReproductionConfig::GenerationEvolution { mutation, .. } =
+            &mut user_config.species[0].reproduction
+        {
+            *mutation = MutationConfig::SelfAdaptive { learning_rate: 0.1 };
+        }
+        let config = EcosystemConfig::from_user_config(user_config).unwrap();
+        let species = &config.species[&SpeciesId::new(0)];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let individual = OrganismIndividual::random(&mut rng, species);
+        let chromosome = individual.chromosome().clone();
+        assert_eq!(chromosome.len(), species.chromosome_layout.len());
+        assert_eq!(chromosome[chromosome.len() - 1], 0.3);
+        assert!(OrganismIndividual::check_chromosome(species, &chromosome, None).is_ok());
+        // Same number of genes, from a species with other traits.
+        let mut other_loci = species
+            .chromosome_layout
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        other_loci.reverse();
+        let other_layout = ga::ChromosomeLayout::new(other_loci);
+        assert!(
+            OrganismIndividual::check_chromosome(species, &chromosome, Some(&other_layout))
+                .is_err()
+        );
+        // The step size gene is not part of the brain.
+        let (_, eye, _, brain) = individual.into_components(species);
+        let n_eye_genes = species.eye.as_ref().unwrap().n_genes();
+        assert!(eye.is_some());
+        assert_eq!(
+            brain.as_chromosome().len(),
+            chromosome.len() - n_eye_genes - 1
+        );
+    }
 }
@@ -32,14 +32,87 @@ impl GenerationEvolutions {
     }
 }
 
+// Past behaviours of each species evolving with the Novelty fitness objective.
+// They are kept out of GenerationEvolutions, which is rebuilt from the config,
+// so that they are saved with the ecosystem.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct NoveltyArchives {
+    pub per_species: BTreeMap<SpeciesId, ga::NoveltyArchive>,
+}
+impl NoveltyArchives {
+    pub fn get_mut(
+        &mut self,
+        config: &SpeciesConfig,
+        fitness: &FitnessConfig,
+    ) -> &mut ga::NoveltyArchive {
+        self.per_species.entry(config.id).or_insert_with(|| {
+            ga::NoveltyArchive::new(fitness.novelty.k_nearest, fitness.novelty.archive_chance)
+        })
+    }
+}
+
+// Crossover method used by all reproductions of the species.
+pub fn crossover_method(
+    config: &SpeciesConfig,
+    crossover: &CrossoverConfig,
+) -> impl ga::CrossoverMethod {
+    // NEAT genomes are aligned by innovation, other genes are left to the inner crossover.
+    // Disabled brain connections are inherited as is, instead of being mixed by the crossover.
+    ga::NeatCrossover::new(ga::KeepDisabled::new(
+        Crossover::new(config, crossover),
+        OrganismIndividual::brain_connection_loci(config),
+    ))
+}
+
+// Mutation method used by all reproductions of the species.
+pub fn mutation_method(
+    config: &SpeciesConfig,
+    mutation: &MutationConfig,
+    mutation_chance: f32,
+    mutation_amplitude: f32,
+) -> impl ga::MutationMethod {
+    let (add_node_chance, add_connection_chance) = match config.brain.kind {
+        BrainKind::Layered => (0.0, 0.0),
+        BrainKind::Neat {
+            add_node_chance,
+            add_connection_chance,
+        } => (add_node_chance, add_connection_chance),
+    };
+    let connection_loci = OrganismIndividual::brain_connection_loci(config);
+    // Brain connections are only switched on or off by the toggle mutation, keeping their weight.
+    (
+        (
+            ga::KeepDisabled::new(
+                Mutation::new(config, mutation, mutation_chance, mutation_amplitude),
+                connection_loci.clone(),
+            ),
+            ga::ToggleMutation::new(
+                config.brain.connection_toggle_chance,
+                connection_loci
+                    .iter()
+                    .map(|connection| connection.enabled)
+                    .collect(),
+            ),
+        ),
+        ga::NeatMutation::new(
+            mutation_chance,
+            mutation_amplitude,
+            add_node_chance,
+            add_connection_chance,
+        ),
+    )
+}
+
 pub struct GenerationEvolution {
     pub config: SpeciesConfig,
     // genetic algorithm
-    pub genetic_algorithm: ga::GeneticAlgorithm<ga::RouletteWheelSelection>,
+    pub genetic_algorithm: ga::GeneticAlgorithm<Selection>,
     //
     pub generation_length: u32,
     pub fertility_rate: f32,
     pub child_spawn_distance: Option<f32>,
+    pub fitness: FitnessConfig,
 }
 impl GenerationEvolution {
     pub fn new(config: &SpeciesConfig) -> Self {
@@ -48,22 +121,180 @@ impl GenerationEvolution {
             fertility_rate,
             mutation_chance,
             mutation_amplitude,
+            ref crossover,
+            ref mutation,
             child_spawn_distance,
+            ref selection,
+            elitism,
+            ref niching,
+            ref fitness,
         } = config.reproduction
         {
+            let mut genetic_algorithm = ga::GeneticAlgorithm::new(
+                Selection::new(selection),
+                crossover_method(config, crossover),
+                mutation_method(config, mutation, mutation_chance, mutation_amplitude),
+            )
+            .with_elitism(elitism)
+            .with_layout(config.chromosome_layout.clone());
+            if let Some(niching) = niching {
+                genetic_algorithm = genetic_algorithm.with_niching(match *niching {
+                    NichingConfig::FitnessSharing { radius, alpha } => {
+                        ga::Niching::fitness_sharing(radius, alpha)
+                    }
+                    NichingConfig::Clustering { threshold } => ga::Niching::clustering(threshold),
+                });
+            }
             Self {
                 config: config.clone(),
                 generation_length,
-                genetic_algorithm: ga::GeneticAlgorithm::new(
-                    ga::RouletteWheelSelection,
-                    ga::UniformCrossover,
-                    ga::GaussianMutation::new(mutation_chance, mutation_amplitude),
-                ),
+                genetic_algorithm,
                 fertility_rate,
                 child_spawn_distance,
+                fitness: fitness.clone(),
             }
         } else {
             panic!("Generation Evolution created for incorrect Config")
         }
     }
 }
+
+// Selection method chosen from the species configuration.
+pub enum Selection {
+    RouletteWheel(ga::RouletteWheelSelection),
+    Tournament(ga::TournamentSelection),
+    Rank(ga::RankSelection),
+    Truncation(ga::TruncationSelection),
+}
+impl Selection {
+    pub fn new(config: &SelectionConfig) -> Self {
+        match *config {
+            SelectionConfig::RouletteWheel => Self::RouletteWheel(ga::RouletteWheelSelection),
+            SelectionConfig::Tournament { size } => {
+                Self::Tournament(ga::TournamentSelection::new(size))
+            }
+            SelectionConfig::Rank => Self::Rank(ga::RankSelection),
+            SelectionConfig::Truncation { ratio } => {
+                Self::Truncation(ga::TruncationSelection::new(ratio))
+            }
+        }
+    }
+}
+impl ga::SelectionMethod for Selection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I
+    where
+        I: ga::Individual,
+    {
+        match self {
+            Self::RouletteWheel(selection) => selection.select(rng, population),
+            Self::Tournament(selection) => selection.select(rng, population),
+            Self::Rank(selection) => selection.select(rng, population),
+            Self::Truncation(selection) => selection.select(rng, population),
+        }
+    }
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], n: usize) -> Vec<&'a I>
+    where
+        I: ga::Individual,
+    {
+        match self {
+            Self::RouletteWheel(selection) => selection.select_many(rng, population, n),
+            Self::Tournament(selection) => selection.select_many(rng, population, n),
+            Self::Rank(selection) => selection.select_many(rng, population, n),
+            Self::Truncation(selection) => selection.select_many(rng, population, n),
+        }
+    }
+}
+
+// Crossover method chosen from the species configuration.
+pub enum Crossover {
+    Uniform(ga::UniformCrossover),
+    SinglePoint(ga::SinglePointCrossover),
+    TwoPoint(ga::TwoPointCrossover),
+    ArithmeticBlend(ga::ArithmeticBlendCrossover),
+    Segment(ga::SegmentCrossover),
+}
+impl Crossover {
+    pub fn new(config: &SpeciesConfig, crossover: &CrossoverConfig) -> Self {
+        match *crossover {
+            CrossoverConfig::Uniform => Self::Uniform(ga::UniformCrossover),
+            CrossoverConfig::SinglePoint => Self::SinglePoint(ga::SinglePointCrossover),
+            CrossoverConfig::TwoPoint => Self::TwoPoint(ga::TwoPointCrossover),
+            CrossoverConfig::ArithmeticBlend { alpha } => {
+                Self::ArithmeticBlend(ga::ArithmeticBlendCrossover::new(alpha))
+            }
+            CrossoverConfig::Segment => Self::Segment(ga::SegmentCrossover::new(
+                config.chromosome_layout.segments(),
+            )),
+        }
+    }
+}
+impl ga::CrossoverMethod for Crossover {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &ga::Chromosome,
+        parent_b: &ga::Chromosome,
+    ) -> ga::Chromosome {
+        match self {
+            Self::Uniform(crossover) => crossover.crossover(rng, parent_a, parent_b),
+            Self::SinglePoint(crossover) => crossover.crossover(rng, parent_a, parent_b),
+            Self::TwoPoint(crossover) => crossover.crossover(rng, parent_a, parent_b),
+            Self::ArithmeticBlend(crossover) => crossover.crossover(rng, parent_a, parent_b),
+            Self::Segment(crossover) => crossover.crossover(rng, parent_a, parent_b),
+        }
+    }
+}
+
+// Mutation method chosen from the species configuration.
+pub enum Mutation {
+    Gaussian(ga::GaussianMutation),
+    Normal(ga::NormalMutation),
+    UniformReset(ga::UniformResetMutation),
+    SelfAdaptive(ga::SelfAdaptiveMutation),
+}
+impl Mutation {
+    pub fn new(
+        config: &SpeciesConfig,
+        mutation: &MutationConfig,
+        mutation_chance: f32,
+        mutation_amplitude: f32,
+    ) -> Self {
+        match *mutation {
+            MutationConfig::Gaussian => Self::Gaussian(ga::GaussianMutation::new(
+                mutation_chance,
+                mutation_amplitude,
+            )),
+            MutationConfig::Normal => {
+                Self::Normal(ga::NormalMutation::new(mutation_chance, mutation_amplitude))
+            }
+            MutationConfig::UniformReset => Self::UniformReset(ga::UniformResetMutation::new(
+                mutation_chance,
+                config
+                    .chromosome_layout
+                    .iter()
+                    .map(|locus| {
+                        if locus.is_bounded() {
+                            (locus.min, locus.max)
+                        } else {
+                            // Unbounded brain genes are reset within their random initialization range.
+                            (-1.0, 1.0)
+                        }
+                    })
+                    .collect(),
+            )),
+            MutationConfig::SelfAdaptive { learning_rate } => Self::SelfAdaptive(
+                ga::SelfAdaptiveMutation::new(mutation_chance, learning_rate),
+            ),
+        }
+    }
+}
+impl ga::MutationMethod for Mutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut ga::Chromosome) {
+        match self {
+            Self::Gaussian(mutation) => mutation.mutate(rng, chromosome),
+            Self::Normal(mutation) => mutation.mutate(rng, chromosome),
+            Self::UniformReset(mutation) => mutation.mutate(rng, chromosome),
+            Self::SelfAdaptive(mutation) => mutation.mutate(rng, chromosome),
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::ecosystem::*;
+
+// File format of an ancestry export.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum PhylogenyFormat {
+    // Tree following the first parent of each organism, the mother for a Birth reproduction.
+    Newick,
+    // Graph with an edge for each parent.
+    Graphml,
+}
+impl PhylogenyFormat {
+    // '.graphml' for Graphml, Newick for any other extension.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("graphml") => PhylogenyFormat::Graphml,
+            _ => PhylogenyFormat::Newick,
+        }
+    }
+}
+
+// An organism of a lineage log.
+pub struct LineageOrganism {
+    pub species: String,
+    pub born: u32,
+    // Step and cause of the death, None when alive at the end of the log.
+    pub death: Option<(u32, DeathCause)>,
+    pub parents: Vec<OrganismId>,
+}
+
+// Survival of the lineages of a species, following the first parent of each organism.
+pub struct SpeciesLineage {
+    pub species: String,
+    pub organisms: usize,
+    pub alive: usize,
+    // Organisms whose first parent is not in the log.
+    pub founders: usize,
+    // Founders with living descendants.
+    pub surviving_founders: usize,
+    // Most recent ancestor of all the living organisms, possibly one of them.
+    pub common_ancestor: Option<OrganismId>,
+}
+
+// Ancestry of the organisms, reconstructed from a lineage log.
+pub struct Lineage {
+    pub organisms: BTreeMap<OrganismId, LineageOrganism>,
+    // Step of the last event of the log.
+    pub last_step: u32,
+}
+impl Lineage {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut organisms = BTreeMap::new();
+        let mut last_step = 0;
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str::<LineageEvent>(line)
+                .map_err(|err| format!("line {}: {err}", i + 1))?;
+            match event {
+                LineageEvent::Birth {
+                    step,
+                    id,
+                    species,
+                    parents,
+                    ..
+                } => {
+                    last_step = step;
+                    organisms.insert(
+                        id,
+                        LineageOrganism {
+                            species,
+                            born: step,
+                            death: None,
+                            parents,
+                        },
+                    );
+                }
+                LineageEvent::Alive {
+                    step,
+                    id,
+                    species,
+                    born,
+                    parents,
+                } => {
+                    last_step = step;
+                    organisms.insert(
+                        id,
+                        LineageOrganism {
+                            species,
+                            born,
+                            death: None,
+                            parents,
+                        },
+                    );
+                }
+                LineageEvent::Death { step, id, cause } => {
+                    last_step = step;
+                    if let Some(organism) = organisms.get_mut(&id) {
+                        organism.death = Some((step, cause));
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            organisms,
+            last_step,
+        })
+    }
+    // First parent of the organism, when it is in the log.
+    fn tree_parent(&self, id: OrganismId) -> Option<OrganismId> {
+        self.organisms[&id]
+            .parents
+            .first()
+            .copied()
+            .filter(|parent| self.organisms.contains_key(parent))
+    }
+    // Names of the species, in order of first appearance.
+    pub fn species(&self) -> Vec<&str> {
+        let mut species = Vec::<&str>::new();
+        for organism in self.organisms.values() {
+            if !species.contains(&organism.species.as_str()) {
+                species.push(&organism.species);
+            }
+        }
+        species
+    }
+    pub fn species_lineage(&self, species: &str) -> SpeciesLineage {
+        let ids = self
+            .organisms
+            .iter()
+            .filter(|(_, organism)| organism.species == species)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let alive = ids
+            .iter()
+            .copied()
+            .filter(|id| self.organisms[id].death.is_none())
+            .collect::<Vec<_>>();
+        // Number of living organisms descending from each organism, itself included.
+        // Parents are always born before their children, so the walk ends at a founder.
+        let mut alive_descendants = BTreeMap::<OrganismId, usize>::new();
+        for &id in alive.iter() {
+            let mut ancestor = Some(id);
+            while let Some(id) = ancestor {
+                *alive_descendants.entry(id).or_default() += 1;
+                ancestor = self.tree_parent(id);
+            }
+        }
+        let founders = ids
+            .iter()
+            .copied()
+            .filter(|id| self.tree_parent(*id).is_none())
+            .collect::<Vec<_>>();
+        let mut common_ancestor = None;
+        let mut ancestor = alive.first().copied();
+        while let Some(id) = ancestor {
+            if alive_descendants[&id] == alive.len() {
+                common_ancestor = Some(id);
+                break;
+            }
+            ancestor = self.tree_parent(id);
+        }
+        SpeciesLineage {
+            species: species.to_string(),
+            organisms: ids.len(),
+            alive: alive.len(),
+            founders: founders.len(),
+            surviving_founders: founders
+                .iter()
+                .filter(|id| alive_descendants.contains_key(id))
+                .count(),
+            common_ancestor,
+        }
+    }
+    pub fn sprint(&self, species: Option<&str>) -> String {
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "Lineage of {} organisms, up to step {}",
+            self.organisms.len(),
+            self.last_step
+        );
+        for name in self.species() {
+            if species.is_some_and(|species| species != name) {
+                continue;
+            }
+            let lineage = self.species_lineage(name);
+            let common_ancestor = match lineage.common_ancestor {
+                Some(id) => format!("{} born at step {}", id.0, self.organisms[&id].born),
+                None => "none".to_string(),
+            };
+            let _ = writeln!(
+                s,
+                "  {:10} - Organisms:{:7} Alive:{:5} Founders:{:5} Surviving_Lineages:{:5} Common_Ancestor: {common_ancestor}",
+                lineage.species,
+                lineage.organisms,
+                lineage.alive,
+                lineage.founders,
+                lineage.surviving_founders,
+            );
+        }
+        s
+    }
+    // Organisms of the species, all of them or only the living ones with all their ancestors.
+    pub fn select(&self, species: Option<&str>, all: bool) -> BTreeSet<OrganismId> {
+        let mut selected = BTreeSet::new();
+        let mut to_visit = self
+            .organisms
+            .iter()
+            .filter(|(_, organism)| species.is_none_or(|species| organism.species == species))
+            .filter(|(_, organism)| all || organism.death.is_none())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        while let Some(id) = to_visit.pop() {
+            if selected.insert(id) {
+                to_visit.extend(
+                    self.organisms[&id]
+                        .parents
+                        .iter()
+                        .filter(|parent| self.organisms.contains_key(parent)),
+                );
+            }
+        }
+        selected
+    }
+    // Newick tree of the selected organisms, with the steps between births as branch lengths.
+    // Organisms without a selected first parent are the children of an unnamed root.
+    pub fn to_newick(&self, selected: &BTreeSet<OrganismId>) -> String {
+        let mut children = BTreeMap::<OrganismId, Vec<OrganismId>>::new();
+        let mut roots = vec![];
+        for &id in selected.iter() {
+            match self
+                .tree_parent(id)
+                .filter(|parent| selected.contains(parent))
+            {
+                Some(parent) => children.entry(parent).or_default().push(id),
+                None => roots.push(id),
+            }
+        }
+        let mut s = String::new();
+        if roots.len() > 1 {
+            s.push('(');
+        }
+        for (i, &root) in roots.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            // Written without recursion, as lineages can be thousands of generations deep.
+            let mut stack = vec![(root, 0)];
+            while let Some((id, next_child)) = stack.last_mut() {
+                let id = *id;
+                let id_children = children.get(&id).map(Vec::as_slice).unwrap_or_default();
+                if *next_child < id_children.len() {
+                    s.push(if *next_child == 0 { '(' } else { ',' });
+                    let child = id_children[*next_child];
+                    *next_child += 1;
+                    stack.push((child, 0));
+                    continue;
+                }
+                if !id_children.is_empty() {
+                    s.push(')');
+                }
+                let organism = &self.organisms[&id];
+                let branch_length = self
+                    .tree_parent(id)
+                    .filter(|parent| selected.contains(parent))
+                    .map_or(0, |parent| organism.born - self.organisms[&parent].born);
+                let _ = write!(
+                    s,
+                    "{}:{branch_length}",
+                    newick_label(&format!("{}_{}", organism.species, id.0))
+                );
+                stack.pop();
+            }
+        }
+        if roots.len() > 1 {
+            s.push(')');
+        }
+        s.push_str(";\n");
+        s
+    }
+    // GraphML graph of the selected organisms, with an edge from each of their selected parents.
+    pub fn to_graphml(&self, selected: &BTreeSet<OrganismId>) -> String {
+        let mut s = String::new();
+        s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        s.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (key, domain, kind) in [
+            ("species", "node", "string"),
+            ("born", "node", "int"),
+            ("died", "node", "int"),
+            ("cause", "node", "string"),
+            ("first_parent", "edge", "boolean"),
+        ] {
+            let _ = writeln!(
+                s,
+                "  <key id=\"{key}\" for=\"{domain}\" attr.name=\"{key}\" attr.type=\"{kind}\"/>"
+            );
+        }
+        s.push_str("  <graph id=\"lineage\" edgedefault=\"directed\">\n");
+        for &id in selected.iter() {
+            let organism = &self.organisms[&id];
+            let _ = write!(
+                s,
+                "    <node id=\"n{}\"><data key=\"species\">{}</data><data key=\"born\">{}</data>",
+                id.0,
+                xml_escape(&organism.species),
+                organism.born
+            );
+            if let Some((died, cause)) = organism.death {
+                let _ = write!(
+                    s,
+                    "<data key=\"died\">{died}</data><data key=\"cause\">{cause:?}</data>"
+                );
+            }
+            s.push_str("</node>\n");
+        }
+        for &id in selected.iter() {
+            for (i, parent) in self.organisms[&id].parents.iter().enumerate() {
+                if selected.contains(parent) {
+                    let _ = writeln!(
+                        s,
+                        "    <edge source=\"n{}\" target=\"n{}\"><data key=\"first_parent\">{}</data></edge>",
+                        parent.0,
+                        id.0,
+                        i == 0
+                    );
+                }
+            }
+        }
+        s.push_str("  </graph>\n</graphml>\n");
+        s
+    }
+}
+
+// Quoted when it contains characters with a meaning in Newick.
+fn newick_label(label: &str) -> String {
+    if label.contains(|c: char| c.is_whitespace() || "()[]',;:".contains(c)) {
+        format!("'{}'", label.replace('\'', "''"))
+    } else {
+        label.to_string()
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Prints the lineages of the log, and exports the ancestry when a path is given.
+// Only the living organisms and their ancestors are exported, unless all is set.
+pub fn export_lineage(
+    path: &Path,
+    species: Option<&str>,
+    output: Option<(&Path, PhylogenyFormat)>,
+    all: bool,
+) -> Result<(), String> {
+    let lineage = Lineage::from_file(path)?;
+    if let Some(species) = species {
+        if !lineage.species().contains(&species) {
+            return Err(format!("no organism of species {species} in the log"));
+        }
+    }
+    println!("{}", lineage.sprint(species));
+    if let Some((output_path, format)) = output {
+        let selected = lineage.select(species, all);
+        if selected.is_empty() {
+            return Err("no living organism to export, use --all to export them all".to_string());
+        }
+        let data = match format {
+            PhylogenyFormat::Newick => lineage.to_newick(&selected),
+            PhylogenyFormat::Graphml => lineage.to_graphml(&selected),
+        };
+        std::fs::write(output_path, data).map_err(|err| err.to_string())?;
+        println!("{} organisms written to '{output_path:?}'", selected.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn organism(species: &str, born: u32, alive: bool, parents: &[u64]) -> LineageOrganism {
+        LineageOrganism {
+            species: species.to_string(),
+            born,
+            death: (!alive).then_some((born + 10, DeathCause::Starved)),
+            parents: parents.iter().map(|id| OrganismId(*id)).collect(),
+        }
+    }
+
+    #[test]
+    fn ancestry() {
+        let lineage = Lineage {
+            organisms: [
+                (0, organism("A", 0, false, &[])),
+                (1, organism("A", 0, false, &[])),
+                (2, organism("A", 5, false, &[0, 1])),
+                (3, organism("A", 8, true, &[2])),
+                (4, organism("A", 9, true, &[2, 1])),
+                (5, organism("B", 0, true, &[])),
+            ]
+            .into_iter()
+            .map(|(id, organism)| (OrganismId(id), organism))
+            .collect(),
+            last_step: 20,
+        };
+        let a = lineage.species_lineage("A");
+        assert_eq!((a.organisms, a.alive), (5, 2));
+        assert_eq!((a.founders, a.surviving_founders), (2, 1));
+        assert_eq!(a.common_ancestor, Some(OrganismId(2)));
+        let selected = lineage.select(Some("A"), false);
+        assert_eq!(selected.len(), 5);
+        assert_eq!(
+            lineage.to_newick(&selected),
+            "(((A_3:3,A_4:4)A_2:5)A_0:0,A_1:0);\n"
+        );
+    }
+}
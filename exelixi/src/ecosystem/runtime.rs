@@ -10,6 +10,9 @@ pub struct EcosystemRuntime {
     pub steps: u32,
     pub generation: BTreeMap<SpeciesId, u32>,
     pub population: BTreeMap<SpeciesId, usize>,
+    // Identifier of the next organism to be born.
+    #[serde(default)]
+    pub next_organism_id: u64,
 }
 impl EcosystemRuntime {
     pub fn new(config: &EcosystemConfig) -> Self {
@@ -23,8 +26,14 @@ impl EcosystemRuntime {
             steps: 0,
             generation,
             population,
+            next_organism_id: 0,
         }
     }
+    pub fn new_organism_id(&mut self) -> OrganismId {
+        let id = OrganismId(self.next_organism_id);
+        self.next_organism_id += 1;
+        id
+    }
     pub fn increment_generation(&mut self, species: &SpeciesId) {
         if let Some(generation) = self.generation.get_mut(species) {
             *generation += 1;
@@ -46,11 +55,74 @@ impl EcosystemRuntime {
     }
 }
 
-pub fn initialize_on_new_config(mut commands: Commands, config: Res<EcosystemConfig>) {
+pub fn initialize_on_new_config(
+    mut commands: Commands,
+    config: Res<EcosystemConfig>,
+    type_registry: Res<AppTypeRegistry>,
+) {
     if config.is_changed() {
         // Rebuild all the 'compiled' resources
         commands.insert_resource(GenerationEvolutions::new(&config));
+        commands.insert_resource(GenePools::new(&config, &type_registry.read()));
+    }
+}
+
+// Replaces the config of the running ecosystem, when the changes are compatible with it,
+// and records them in the statistics. Returns the applied changes.
+pub fn apply_config_change(
+    world: &mut World,
+    mut new_config: EcosystemConfig,
+) -> Result<Vec<String>, String> {
+    let config = world.resource::<EcosystemConfig>();
+    // Statistics aggregation can be overridden from the command line, it is kept as is.
+    new_config.statistics_aggregation_start = config.statistics_aggregation_start;
+    new_config.statistics_aggregation_rate = config.statistics_aggregation_rate;
+    let changes = config.hot_reload_changes(&new_config)?;
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+    // Organs keep a copy of their config.
+    for (organism, mut body, eye, leaf, locomotion, mouth, uterus) in world
+        .query::<(
+            &Organism,
+            &mut Body,
+            Option<&mut Eye>,
+            Option<&mut Leaf>,
+            Option<&mut Locomotion>,
+            Option<&mut Mouth>,
+            Option<&mut Uterus>,
+        )>()
+        .iter_mut(world)
+    {
+        let species = &new_config.species[&organism.species];
+        body.update_config(&species.body);
+        if let (Some(mut eye), Some(config)) = (eye, &species.eye) {
+            eye.update_config(config);
+        }
+        if let (Some(mut leaf), Some(config)) = (leaf, &species.leaf) {
+            leaf.update_config(config);
+        }
+        if let (Some(mut locomotion), Some(config)) = (locomotion, &species.locomotion) {
+            locomotion.update_config(config);
+        }
+        if let (Some(mut mouth), Some(config)) = (mouth, &species.mouth) {
+            mouth.update_config(config);
+        }
+        if let (Some(mut uterus), Some(config)) = (uterus, &species.uterus) {
+            uterus.update_config(config);
+        }
     }
+    // Rebuilt now rather than by initialize_on_new_config, so that the next steps already use them.
+    world.insert_resource(GenerationEvolutions::new(&new_config));
+    let gene_pools = GenePools::new(&new_config, &world.resource::<AppTypeRegistry>().read());
+    world.insert_resource(gene_pools);
+    let max_length = new_config.statistics_max_length as usize;
+    world.insert_resource(new_config);
+    let steps = world.resource::<EcosystemRuntime>().steps;
+    world
+        .resource_mut::<EcosystemStatistics>()
+        .add_config_changes(steps, changes.clone(), max_length);
+    Ok(changes)
 }
 
 // At the beginning of the simulation, ensure each species' population is at the minimum.
@@ -80,6 +152,8 @@ pub fn ensure_minimum_population(
                 position: None,
                 energy: None,
                 chromosome: Chromosome::default(),
+                origin: BirthOrigin::MinimumPopulation,
+                parents: vec![],
             });
         }
     }
@@ -1,37 +1,206 @@
+use bevy::ecs::entity::EntityHashMap;
+
 use crate::ecosystem::*;
 
-pub fn save_ecosystem_to_file(path: &PathBuf, world: &mut World) {
-    // Save Entities, using Bevy Dynamic Scene
-    let scene = {
-        let mut organisms = world.query_filtered::<Entity, With<Organism>>();
-        DynamicSceneBuilder::from_world(world)
-            .deny_all()
-            .allow::<Position>()
-            .allow::<Organism>()
-            .allow::<Body>()
-            .allow::<Brain>()
-            .allow::<Mouth>()
-            .allow::<Leaf>()
-            .allow::<Locomotion>()
-            .allow::<Eye>()
-            .deny_all_resources()
-            .allow_resource::<GlobalEntropy<WyRand>>()
-            .allow_resource::<EcosystemConfig>()
-            .allow_resource::<EcosystemRuntime>()
-            .allow_resource::<EcosystemStatistics>()
-            .extract_entities(organisms.iter(world))
-            .extract_resources()
-            .build()
-    };
+pub fn save_ecosystem_to_file(
+    path: &PathBuf,
+    format: SaveFormat,
+    world: &mut World,
+) -> Result<(), SaveError> {
+    let world_ser = serialize_ecosystem(world, format)?;
+    std::fs::write(path, world_ser)?;
+    info!("Ecosystem has been saved to '{:?}'", path);
+    Ok(())
+}
+
+// Save content, the header line followed by the scene.
+pub fn serialize_ecosystem(world: &mut World, format: SaveFormat) -> Result<Vec<u8>, SaveError> {
+    let scene = ecosystem_scene(world);
     info!(
         "Saved {} entities and {} resources",
         scene.entities.len(),
         scene.resources.len()
     );
     let type_registry = world.resource::<AppTypeRegistry>().read();
-    let world_ser = scene
-        .serialize(&type_registry)
-        .expect("Scene serialization failed.");
-    std::fs::write(path, world_ser.as_bytes()).expect("Failed to write to save path");
-    info!("Ecosystem has been saved to '{:?}'", path);
+    let header = ron::to_string(&SaveHeader::current(format))
+        .map_err(|err| SaveError::Serialization(err.to_string()))?;
+    let mut data = format!("{header}\n").into_bytes();
+    data.extend(format.encode(&scene, &type_registry)?);
+    Ok(data)
+}
+
+// Every component and resource the simulation steps depend on,
+// so that a loaded ecosystem runs exactly as the saved one would have.
+pub fn ecosystem_scene(world: &mut World) -> DynamicScene {
+    // Save Entities, using Bevy Dynamic Scene
+    let entities = world
+        .query_filtered::<Entity, With<Organism>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    let mut scene = DynamicSceneBuilder::from_world(world)
+        .deny_all()
+        .allow::<Position>()
+        .allow::<Organism>()
+        .allow::<Body>()
+        .allow::<Brain>()
+        .allow::<Mouth>()
+        .allow::<Leaf>()
+        .allow::<Locomotion>()
+        .allow::<Eye>()
+        .allow::<Uterus>()
+        .deny_all_resources()
+        .allow_resource::<GlobalEntropy<WyRand>>()
+        .allow_resource::<EcosystemConfig>()
+        .allow_resource::<EcosystemRuntime>()
+        .allow_resource::<EcosystemStatistics>()
+        .allow_resource::<StatisticsFlows>()
+        .allow_resource::<OrganismsLifecycle>()
+        .allow_resource::<NoveltyArchives>()
+        .extract_entities(entities.iter().copied())
+        .extract_resources()
+        .build();
+    // The builder sorts entities by id, they are put back in query order
+    // so that the loaded organisms are iterated in the same order as the saved ones.
+    let order = entities
+        .iter()
+        .enumerate()
+        .map(|(i, entity)| (*entity, i))
+        .collect::<EntityHashMap<_>>();
+    scene.entities.sort_by_key(|entity| order[&entity.entity]);
+    scene
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::reflect::serde::ReflectSerializer;
+
+    fn new_app(config: &str, seed: u64) -> App {
+        let mut app = App::new();
+        app.add_plugins(EcosystemPlugin {
+            seed: Some(seed),
+            config_path: Some(PathBuf::from(format!(
+                "{}/../configs/{config}",
+                env!("CARGO_MANIFEST_DIR")
+            ))),
+            override_stats_start: None,
+            override_stats_rate: None,
+            statistics_outputs: vec![],
+            statistics_console: false,
+            lineage_output: None,
+        });
+        app
+    }
+
+    fn run(app: &mut App, n_steps: u32) {
+        for _ in 0..n_steps {
+            app.world_mut().run_schedule(EcosystemSchedule);
+        }
+    }
+
+    // Saved components and resources, without the entity ids that change on load.
+    // Components of each entity are sorted, their order depending on when their type was registered.
+    fn snapshot(app: &mut App) -> Vec<String> {
+        let scene = ecosystem_scene(app.world_mut());
+        let type_registry = app.world().resource::<AppTypeRegistry>().read();
+        let serialize = |value: &dyn Reflect| {
+            ron::to_string(&ReflectSerializer::new(value, &type_registry)).unwrap()
+        };
+        scene
+            .entities
+            .iter()
+            .flat_map(|entity| {
+                let mut components = entity
+                    .components
+                    .iter()
+                    .map(|component| serialize(component.as_ref()))
+                    .collect::<Vec<_>>();
+                components.sort();
+                components
+            })
+            .chain(
+                scene
+                    .resources
+                    .iter()
+                    .map(|resource| serialize(resource.as_ref())),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn save_load_is_reproducible() {
+        for config in ["Birth.ron", "bench_A.ron"] {
+            let mut uninterrupted = new_app(config, 0);
+            run(&mut uninterrupted, 1200);
+            let expected = snapshot(&mut uninterrupted);
+
+            let mut saved = new_app(config, 0);
+            run(&mut saved, 600);
+            for format in [SaveFormat::Ron, SaveFormat::Binary, SaveFormat::Compressed] {
+                let data = serialize_ecosystem(saved.world_mut(), format).unwrap();
+                // A different seed, all the state must come from the save.
+                let mut loaded = new_app(config, 1);
+                deserialize_ecosystem(&data, loaded.world_mut()).unwrap();
+                run(&mut loaded, 600);
+
+                assert_eq!(expected, snapshot(&mut loaded), "{config} {format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn baseline_save_is_migrated() {
+        // Headerless save in the format of the first version, written by hand
+        // with a plant and two herbivores, and without random state.
+        // The first herbivore was born from evolution, the second one spawned at random,
+        // without chromosome.
+        let data = include_str!("../../../tests/saves/baseline.ecosim");
+        let mut app = new_app("default.ron", 0);
+        deserialize_ecosystem(data.as_bytes(), app.world_mut()).unwrap();
+        let config = app.world().resource::<EcosystemConfig>();
+        assert_eq!(
+            config.species[&SpeciesId::new(0)].chromosome_layout.len(),
+            55
+        );
+        let mut brains = app.world_mut().query::<(&Organism, &Brain)>();
+        assert_eq!(brains.iter(app.world()).count(), 2);
+        for (organism, brain) in brains.iter(app.world()) {
+            assert_eq!(
+                brain.as_chromosome().iter().collect::<Vec<_>>(),
+                organism.chromosome.iter().collect::<Vec<_>>()
+            );
+            assert_eq!(brain.nn.n_active_connections(), 24);
+        }
+        // Past the end of the first generation, evolving both herbivores.
+        run(&mut app, 150);
+        let runtime = app.world().resource::<EcosystemRuntime>();
+        assert_eq!(runtime.generation[&SpeciesId::new(0)], 1);
+
+        // Saved values still missing a field are an error, not a panic.
+        let mut app = new_app("default.ron", 0);
+        let data = data.replacen("energy_eaten: 0.0,", "", 1);
+        assert!(matches!(
+            deserialize_ecosystem(data.as_bytes(), app.world_mut()),
+            Err(SaveError::Deserialization(_))
+        ));
+    }
+
+    #[test]
+    fn newer_save_format_is_an_error() {
+        let mut app = new_app("Birth.ron", 0);
+        let data = serialize_ecosystem(app.world_mut(), SaveFormat::Ron).unwrap();
+        let data = String::from_utf8(data).unwrap().replacen(
+            &format!("format_version:{SAVE_FORMAT_VERSION}"),
+            &format!("format_version:{}", SAVE_FORMAT_VERSION + 1),
+            1,
+        );
+        assert!(matches!(
+            deserialize_ecosystem(data.as_bytes(), app.world_mut()),
+            Err(SaveError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            deserialize_ecosystem(b"(", app.world_mut()),
+            Err(SaveError::Deserialization(_))
+        ));
+    }
 }
@@ -0,0 +1,838 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use bevy::reflect::{
+    DynamicEnum, DynamicStruct, Enum, FromReflect, ReflectMut, ReflectRef, TypePath, TypeRegistry,
+};
+use bevy::scene::{serde::SceneDeserializer, serde::SceneSerializer};
+use bincode::Options;
+use lib_genetic_algorithm::Individual;
+use serde::de::DeserializeSeed;
+
+use crate::ecosystem::*;
+
+// Version of the content of save files.
+// It must be increased, with a migration from the previous version,
+// whenever a saved component or resource changes in a way older saves cannot be read.
+pub const SAVE_FORMAT_VERSION: u32 = 10;
+
+// Encoding of the saved scene.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum SaveFormat {
+    // Human readable RON, for debugging.
+    #[default]
+    Ron,
+    // Bincode, compact and fast to write and read.
+    Binary,
+    // Bincode compressed with zstd, for large worlds.
+    Compressed,
+}
+impl SaveFormat {
+    // Format matching the extension of the save path:
+    // '.bin' for Binary, '.zst' for Compressed and Ron for any other.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("bin") => SaveFormat::Binary,
+            Some("zst") => SaveFormat::Compressed,
+            _ => SaveFormat::Ron,
+        }
+    }
+    // Extension of the save files written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SaveFormat::Ron => "ecosim",
+            SaveFormat::Binary => "bin",
+            SaveFormat::Compressed => "zst",
+        }
+    }
+    pub fn encode(
+        &self,
+        scene: &DynamicScene,
+        type_registry: &TypeRegistry,
+    ) -> Result<Vec<u8>, SaveError> {
+        let serialization_error =
+            |err: &dyn fmt::Display| SaveError::Serialization(err.to_string());
+        match self {
+            SaveFormat::Ron => scene
+                .serialize(type_registry)
+                .map(String::into_bytes)
+                .map_err(|err| serialization_error(&err)),
+            SaveFormat::Binary | SaveFormat::Compressed => {
+                let data = bincode_options()
+                    .serialize(&SceneSerializer::new(scene, type_registry))
+                    .map_err(|err| serialization_error(&err))?;
+                if *self == SaveFormat::Compressed {
+                    Ok(zstd::encode_all(data.as_slice(), ZSTD_LEVEL)?)
+                } else {
+                    Ok(data)
+                }
+            }
+        }
+    }
+    pub fn decode(
+        &self,
+        data: &[u8],
+        type_registry: &TypeRegistry,
+    ) -> Result<DynamicScene, SaveError> {
+        let deserialization_error =
+            |err: &dyn fmt::Display| SaveError::Deserialization(err.to_string());
+        let scene_deserializer = SceneDeserializer { type_registry };
+        match self {
+            SaveFormat::Ron => {
+                let mut deserializer = ron::de::Deserializer::from_bytes(data)
+                    .map_err(|err| deserialization_error(&err))?;
+                scene_deserializer
+                    .deserialize(&mut deserializer)
+                    .map_err(|err| deserialization_error(&err))
+            }
+            SaveFormat::Binary => bincode_options()
+                .deserialize_seed(scene_deserializer, data)
+                .map_err(|err| deserialization_error(&err)),
+            SaveFormat::Compressed => bincode_options()
+                .deserialize_seed(scene_deserializer, &zstd::decode_all(data)?)
+                .map_err(|err| deserialization_error(&err)),
+        }
+    }
+}
+
+// Compression level favoring speed, so that saving stays cheap enough for frequent checkpoints.
+const ZSTD_LEVEL: i32 = 3;
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new().with_fixint_encoding()
+}
+
+// First line of a save file, before the saved scene.
+// Saves without header are RON saves from version 1.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SaveHeader {
+    pub format_version: u32,
+    // Version of the crate that produced the save, for information only.
+    pub crate_version: String,
+    #[serde(default)]
+    pub format: SaveFormat,
+}
+impl SaveHeader {
+    pub fn current(format: SaveFormat) -> Self {
+        Self {
+            format_version: SAVE_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            format,
+        }
+    }
+    // Splits the header from the saved scene.
+    pub fn split(data: &[u8]) -> (Self, &[u8]) {
+        if let Some(end) = data.iter().position(|byte| *byte == b'\n') {
+            if let Some(header) = std::str::from_utf8(&data[..end])
+                .ok()
+                .and_then(|first_line| ron::from_str::<SaveHeader>(first_line).ok())
+            {
+                return (header, &data[end + 1..]);
+            }
+        }
+        let header = Self {
+            format_version: 1,
+            crate_version: "unknown".to_string(),
+            format: SaveFormat::Ron,
+        };
+        (header, data)
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    // The save was produced by a newer version of the format.
+    UnsupportedVersion(SaveHeader),
+    Serialization(String),
+    Deserialization(String),
+    Migration { from_version: u32, message: String },
+    Spawn(String),
+    // An exported organism does not fit the species it is imported into.
+    IncompatibleOrganism(String),
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{err}"),
+            SaveError::UnsupportedVersion(header) => write!(
+                f,
+                "save format version {} (exelixi {}) is newer than the supported version {SAVE_FORMAT_VERSION}",
+                header.format_version, header.crate_version
+            ),
+            SaveError::Serialization(message) => write!(f, "invalid ecosystem state: {message}"),
+            SaveError::Deserialization(message) => write!(f, "invalid save content: {message}"),
+            SaveError::Migration {
+                from_version,
+                message,
+            } => write!(
+                f,
+                "save could not be migrated from format version {from_version}: {message}"
+            ),
+            SaveError::IncompatibleOrganism(message) => {
+                write!(f, "incompatible organism: {message}")
+            }
+            SaveError::Spawn(message) => write!(f, "saved ecosystem could not be spawned: {message}"),
+        }
+    }
+}
+impl std::error::Error for SaveError {}
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+// Upgrade of a save from one format version to the next.
+pub struct Migration {
+    pub from_version: u32,
+    // Changes to the saved text, before it is deserialized, for renamed types or fields.
+    pub text: Option<fn(&str) -> String>,
+    // Changes to the deserialized scene, for added components or resources.
+    pub scene: Option<fn(&mut DynamicScene) -> Result<(), String>>,
+}
+
+// All migrations, ordered by version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from_version: 1,
+        text: Some(wrap_layered_networks),
+        scene: Some(complete_version_1),
+    },
+    Migration {
+        from_version: 2,
+        text: None,
+        scene: Some(add_missing_gene_pool),
+    },
+    Migration {
+        from_version: 3,
+        text: None,
+        scene: Some(add_missing_config_changes),
+    },
+    Migration {
+        from_version: 4,
+        text: None,
+        scene: Some(add_birth_origins_and_flows),
+    },
+    Migration {
+        from_version: 5,
+        text: None,
+        scene: Some(add_missing_traits),
+    },
+    Migration {
+        from_version: 6,
+        text: None,
+        scene: Some(add_organism_ids),
+    },
+    Migration {
+        from_version: 7,
+        text: None,
+        scene: Some(add_statistics_max_length),
+    },
+    Migration {
+        from_version: 8,
+        text: None,
+        scene: Some(rebuild_empty_chromosomes),
+    },
+    Migration {
+        from_version: 9,
+        text: None,
+        scene: Some(add_enabled_genes),
+    },
+];
+
+// Applies the text migrations needed to bring a save at the given version to the current one.
+// Binary saves cannot be changed before being deserialized, they must be migrated
+// by loading and saving them as RON with the version that produced them.
+pub fn migrate_text(header: &SaveHeader, data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    let mut data = data.to_vec();
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| m.from_version >= header.format_version)
+    {
+        if let Some(text) = migration.text {
+            if header.format != SaveFormat::Ron {
+                return Err(SaveError::Migration {
+                    from_version: migration.from_version,
+                    message: format!(
+                        "{:?} saves can not be migrated, only Ron ones",
+                        header.format
+                    ),
+                });
+            }
+            let text_data = std::str::from_utf8(&data)
+                .map_err(|err| SaveError::Deserialization(err.to_string()))?;
+            data = text(text_data).into_bytes();
+        }
+    }
+    Ok(data)
+}
+
+// Applies the scene migrations needed to bring a save at the given version to the current one.
+pub fn migrate_scene(version: u32, scene: &mut DynamicScene) -> Result<(), SaveError> {
+    for migration in MIGRATIONS.iter().filter(|m| m.from_version >= version) {
+        if let Some(migrate) = migration.scene {
+            migrate(scene).map_err(|message| SaveError::Migration {
+                from_version: migration.from_version,
+                message,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+// Version 1 brains were always a layered network, not one of the network kinds.
+fn wrap_layered_networks(text: &str) -> String {
+    const BRAIN: &str = "\"exelixi::ecosystem::organism::organs::brain::Brain\":";
+    let mut migrated = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(BRAIN) {
+        let (before, brain) = rest.split_at(start + BRAIN.len());
+        migrated.push_str(before);
+        rest = brain;
+        // The network is the only field of version 1 brains.
+        let Some(field_end) = brain.find("nn:").map(|start| start + "nn:".len()) else {
+            continue;
+        };
+        let network_start = brain.len() - brain[field_end..].trim_start().len();
+        let Some(network_len) = parenthesized_len(&brain[network_start..]) else {
+            continue;
+        };
+        let network_end = network_start + network_len;
+        migrated.push_str(&brain[..network_start]);
+        migrated.push_str("Layered(");
+        migrated.push_str(&brain[network_start..network_end]);
+        migrated.push(')');
+        rest = &brain[network_end..];
+    }
+    migrated.push_str(rest);
+    migrated
+}
+
+// Length of the parenthesized value the text starts with, parentheses included.
+fn parenthesized_len(text: &str) -> Option<usize> {
+    if !text.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Version 1 saves predate the lifecycle queues, configurable brains and reproduction operators.
+// Added fields take the values that behave as version 1 did.
+fn complete_version_1(scene: &mut DynamicScene) -> Result<(), String> {
+    add_missing_uterus(scene)?;
+    for entity in scene.entities.iter_mut() {
+        for component in entity.components.iter_mut() {
+            if component.represents::<Brain>() {
+                complete_version_1_brain(dynamic_struct(component.as_mut(), "Brain")?)?;
+            } else if component.represents::<Organism>() {
+                let organism = dynamic_struct(component.as_mut(), "Organism")?;
+                organism.insert("offspring", 0u32);
+                let chromosome = organism
+                    .field_mut("chromosome")
+                    .ok_or("missing Organism chromosome")?;
+                dynamic_struct(chromosome, "Chromosome")?
+                    .insert("neat", Option::<ga::NeatGenome>::None);
+            } else if component.represents::<Locomotion>() {
+                dynamic_struct(component.as_mut(), "Locomotion")?.insert("distance", 0.0f32);
+            } else if component.represents::<Mouth>() {
+                dynamic_struct(component.as_mut(), "Mouth")?.insert("energy_eaten_total", 0.0f32);
+            }
+        }
+    }
+    let config = dynamic_resource::<EcosystemConfig>(scene)?;
+    let Some(ReflectMut::Map(species)) = config
+        .field_mut("species")
+        .map(|species| species.reflect_mut())
+    else {
+        return Err("EcosystemConfig species is not a map".to_string());
+    };
+    for i in 0..species.len() {
+        let species_config = species
+            .get_at_mut(i)
+            .and_then(|(_, species_config)| species_config.downcast_mut::<DynamicStruct>())
+            .ok_or("SpeciesConfig is not a struct")?;
+        // The default brain is the fixed topology of version 1.
+        species_config.insert("brain", BrainConfig::default());
+        // Layouts are computed again from the config once loaded.
+        species_config.insert("chromosome_layout", ga::ChromosomeLayout::default());
+        let reproduction = species_config
+            .field_mut("reproduction")
+            .and_then(|reproduction| reproduction.downcast_mut::<DynamicEnum>())
+            .ok_or("ReproductionConfig is not an enum")?;
+        complete_version_1_reproduction(reproduction);
+    }
+    for_each_species_statistic(scene, |statistic| {
+        statistic.insert("niches", Option::<u32>::None);
+    })?;
+    // The following migrations complete the lifecycle queues, which were not saved either.
+    scene
+        .resources
+        .push(Box::new(OrganismsLifecycle::default().clone_dynamic()));
+    scene
+        .resources
+        .push(Box::new(NoveltyArchives::default().clone_dynamic()));
+    Ok(())
+}
+
+// Version 1 networks had no disabled connection, no recurrent layer and Relu everywhere.
+fn complete_version_1_brain(brain: &mut DynamicStruct) -> Result<(), String> {
+    let Some(ReflectMut::Enum(network)) = brain.field_mut("nn").map(|nn| nn.reflect_mut()) else {
+        return Err("Brain nn is not an enum".to_string());
+    };
+    let network = network.field_at_mut(0).ok_or("missing Brain network")?;
+    let Some(ReflectMut::List(layers)) = dynamic_struct(network, "Network")?
+        .field_mut("layers")
+        .map(|layers| layers.reflect_mut())
+    else {
+        return Err("Network layers is not a list".to_string());
+    };
+    let n_layers = layers.len();
+    for i in 0..n_layers {
+        let layer = dynamic_struct(layers.get_mut(i).ok_or("missing Layer")?, "Layer")?;
+        layer.insert("activation", nn::Activation::Relu);
+        layer.insert("recurrent", false);
+        let Some(ReflectMut::List(neurons)) = layer
+            .field_mut("neurons")
+            .map(|neurons| neurons.reflect_mut())
+        else {
+            return Err("Layer neurons is not a list".to_string());
+        };
+        for j in 0..neurons.len() {
+            let neuron = dynamic_struct(neurons.get_mut(j).ok_or("missing Neuron")?, "Neuron")?;
+            let Some(ReflectRef::List(weights)) =
+                neuron.field("weights").map(|weights| weights.reflect_ref())
+            else {
+                return Err("Neuron weights is not a list".to_string());
+            };
+            let enabled = vec![true; weights.len()];
+            neuron.insert("enabled", enabled);
+        }
+    }
+    // Empty memory of a network without recurrent layer.
+    brain.insert("memory", vec![Vec::<f32>::new(); n_layers]);
+    brain.insert("energy_cost", 0.0f32);
+    Ok(())
+}
+
+// Version 1 reproductions used roulette wheel selection, uniform crossover and Gaussian mutation,
+// which are the defaults of the added fields.
+fn complete_version_1_reproduction(reproduction: &mut DynamicEnum) {
+    let mut fields = DynamicStruct::default();
+    for field in reproduction.iter_fields() {
+        if let Some(name) = field.name() {
+            fields.insert_boxed(name, field.value().clone_value());
+        }
+    }
+    match reproduction.variant_name() {
+        "GenerationEvolution" => {
+            fields.insert("crossover", CrossoverConfig::default());
+            fields.insert("mutation", MutationConfig::default());
+            fields.insert("selection", SelectionConfig::default());
+            fields.insert("elitism", 0usize);
+            fields.insert("niching", Option::<NichingConfig>::None);
+            fields.insert("fitness", FitnessConfig::default());
+        }
+        "Birth" => {
+            fields.insert("crossover", CrossoverConfig::default());
+            fields.insert("mutation", MutationConfig::default());
+        }
+        _ => return,
+    }
+    let variant_name = reproduction.variant_name().to_string();
+    reproduction.set_variant(variant_name, fields);
+}
+
+// Version 1 saves did not include the Uterus of organisms.
+// They are given back an empty one, as if they had not mated yet.
+fn add_missing_uterus(scene: &mut DynamicScene) -> Result<(), String> {
+    // Only the fields needed are read, as later versions added others to these types.
+    let config = scene
+        .resources
+        .iter()
+        .find(|resource| resource.represents::<EcosystemConfig>())
+        .ok_or("missing EcosystemConfig")?;
+    let ReflectRef::Struct(config) = config.reflect_ref() else {
+        return Err("EcosystemConfig is not a struct".to_string());
+    };
+    let Some(ReflectRef::Map(species_configs)) =
+        config.field("species").map(|species| species.reflect_ref())
+    else {
+        return Err("EcosystemConfig species is not a map".to_string());
+    };
+    for entity in scene.entities.iter_mut() {
+        let species = entity
+            .components
+            .iter()
+            .find(|component| component.represents::<Organism>())
+            .and_then(|component| match component.reflect_ref() {
+                ReflectRef::Struct(organism) => organism.field("species"),
+                _ => None,
+            })
+            .and_then(SpeciesId::from_reflect)
+            .ok_or("entity without Organism")?;
+        let uterus_config = species_configs
+            .get(&species)
+            .and_then(|species_config| match species_config.reflect_ref() {
+                ReflectRef::Struct(species_config) => species_config.field("uterus"),
+                _ => None,
+            })
+            .and_then(Option::<UterusConfig>::from_reflect)
+            .ok_or("organism of an unconfigured species")?;
+        if let Some(uterus_config) = &uterus_config {
+            if !entity
+                .components
+                .iter()
+                .any(|component| component.represents::<Uterus>())
+            {
+                entity.components.push(Box::new(Uterus::new(uterus_config)));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Version 2 species configs did not have a gene pool.
+fn add_missing_gene_pool(scene: &mut DynamicScene) -> Result<(), String> {
+    let config = scene
+        .resources
+        .iter_mut()
+        .find(|resource| resource.represents::<EcosystemConfig>())
+        .ok_or("missing EcosystemConfig")?;
+    let ReflectMut::Struct(config) = config.reflect_mut() else {
+        return Err("EcosystemConfig is not a struct".to_string());
+    };
+    let Some(ReflectMut::Map(species)) = config
+        .field_mut("species")
+        .map(|species| species.reflect_mut())
+    else {
+        return Err("EcosystemConfig species is not a map".to_string());
+    };
+    for i in 0..species.len() {
+        let species_config = species
+            .get_at_mut(i)
+            .and_then(|(_, species_config)| species_config.downcast_mut::<DynamicStruct>())
+            .ok_or("SpeciesConfig is not a struct")?;
+        if species_config.field("gene_pool").is_none() {
+            species_config.insert("gene_pool", Option::<GenePoolConfig>::None);
+        }
+    }
+    Ok(())
+}
+
+// Version 3 statistics did not record config changes.
+fn add_missing_config_changes(scene: &mut DynamicScene) -> Result<(), String> {
+    let statistics = dynamic_resource::<EcosystemStatistics>(scene)?;
+    if statistics.field("config_changes").is_none() {
+        statistics.insert("config_changes", Vec::<(u32, Vec<String>)>::new());
+    }
+    Ok(())
+}
+
+// Version 4 births had no origin, deaths no cause, and statistics no ages and flows.
+fn add_birth_origins_and_flows(scene: &mut DynamicScene) -> Result<(), String> {
+    let lifecycle = dynamic_resource::<OrganismsLifecycle>(scene)?;
+    // Deaths are applied within a step, the saved ones are always empty.
+    lifecycle.insert("deaths", Vec::<(Entity, DeathCause)>::new());
+    // Births pending between steps can only be imported organisms.
+    if let Some(ReflectMut::List(births)) = lifecycle
+        .field_mut("births")
+        .map(|births| births.reflect_mut())
+    {
+        for i in 0..births.len() {
+            let birth = births.get_mut(i).ok_or("missing OrganismBirth")?;
+            dynamic_struct(birth, "OrganismBirth")?.insert("origin", BirthOrigin::Import);
+        }
+    }
+    for_each_species_statistic(scene, |statistic| {
+        statistic.insert("age_mean", 0.0f32);
+        statistic.insert("age_min", 0u32);
+        statistic.insert("age_max", 0u32);
+        statistic.insert("flows", SpeciesFlows::default());
+    })
+}
+
+// Version 5 statistics had no trait distributions and genetic diversity.
+fn add_missing_traits(scene: &mut DynamicScene) -> Result<(), String> {
+    for_each_species_statistic(scene, |statistic| {
+        statistic.insert("traits", SpeciesTraits::default());
+    })
+}
+
+// Version 6 organisms had no id and no parents.
+// They are numbered in the order of the scene, their parents are unknown.
+fn add_organism_ids(scene: &mut DynamicScene) -> Result<(), String> {
+    let mut next_organism_id = 0;
+    for entity in scene.entities.iter_mut() {
+        for component in entity.components.iter_mut() {
+            if component.represents::<Organism>() {
+                let organism = dynamic_struct(component.as_mut(), "Organism")?;
+                organism.insert("id", OrganismId(next_organism_id));
+                organism.insert("parents", Vec::<OrganismId>::new());
+                next_organism_id += 1;
+            } else if component.represents::<Uterus>() {
+                // Uterus added by the version 1 migration are already complete.
+                if let Some(uterus) = component.downcast_mut::<DynamicStruct>() {
+                    uterus.insert("mate", Option::<OrganismId>::None);
+                }
+            }
+        }
+    }
+    dynamic_resource::<EcosystemRuntime>(scene)?.insert("next_organism_id", next_organism_id);
+    let lifecycle = dynamic_resource::<OrganismsLifecycle>(scene)?;
+    if let Some(ReflectMut::List(births)) = lifecycle
+        .field_mut("births")
+        .map(|births| births.reflect_mut())
+    {
+        for i in 0..births.len() {
+            let birth = births.get_mut(i).ok_or("missing OrganismBirth")?;
+            dynamic_struct(birth, "OrganismBirth")?.insert("parents", Vec::<OrganismId>::new());
+        }
+    }
+    Ok(())
+}
+
+// Version 7 statistics were never downsampled.
+fn add_statistics_max_length(scene: &mut DynamicScene) -> Result<(), String> {
+    dynamic_resource::<EcosystemConfig>(scene)?
+        .insert("statistics_max_length", DEFAULT_STATISTICS_MAX_LENGTH);
+    for_each_species_statistic(scene, |statistic| {
+        statistic.insert("envelope", Option::<StatisticEnvelope>::None);
+    })
+}
+
+// Version 1 organisms spawned at random were saved with an empty chromosome.
+// It is rebuilt from their organs, so that they take part in the reproduction of their species.
+// This is done once the scene is complete up to version 8, for the organs to be read.
+fn rebuild_empty_chromosomes(scene: &mut DynamicScene) -> Result<(), String> {
+    let config = scene
+        .resources
+        .iter()
+        .find(|resource| resource.represents::<EcosystemConfig>())
+        .and_then(|config| EcosystemConfig::from_reflect(config.as_reflect()))
+        .ok_or("missing EcosystemConfig")?;
+    for entity in scene.entities.iter_mut() {
+        let Some(organism) = component::<Organism>(&entity.components) else {
+            continue;
+        };
+        let species_config = config
+            .species
+            .get(&organism.species)
+            .ok_or_else(|| format!("unknown species {:?}", organism.species))?;
+        // Organisms without eye have no brain, and an empty chromosome.
+        if !organism.chromosome.is_empty() || species_config.eye.is_none() {
+            continue;
+        }
+        let (Some(body), Some(brain)) = (
+            component::<Body>(&entity.components),
+            component::<Brain>(&entity.components),
+        ) else {
+            return Err(format!("organism {:?} has no Body or Brain", organism.id));
+        };
+        let eye = component::<Eye>(&entity.components);
+        let individual =
+            OrganismIndividual::from_components(species_config, &body, &eye.as_ref(), &brain);
+        let organism = entity
+            .components
+            .iter_mut()
+            .find(|component| component.represents::<Organism>())
+            .ok_or("missing Organism")?;
+        dynamic_struct(organism.as_mut(), "Organism")?
+            .insert("chromosome", individual.chromosome().clone());
+    }
+    Ok(())
+}
+
+// Version 9 brain chromosomes had no enabled flags, disabled connections had a weight of 0.0.
+// Chromosomes of organisms, of their mates and of pending births are given a flag per connection,
+// enabled unless its weight is 0.0.
+fn add_enabled_genes(scene: &mut DynamicScene) -> Result<(), String> {
+    let config = scene
+        .resources
+        .iter()
+        .find(|resource| resource.represents::<EcosystemConfig>())
+        .and_then(|config| EcosystemConfig::from_reflect(config.as_reflect()))
+        .ok_or("missing EcosystemConfig")?;
+    let layout = |species: &SpeciesId| {
+        config
+            .species
+            .get(species)
+            .map(OrganismIndividual::chromosome_layout)
+            .ok_or_else(|| format!("unknown species {species:?}"))
+    };
+    for entity in scene.entities.iter_mut() {
+        let Some(organism) = component::<Organism>(&entity.components) else {
+            continue;
+        };
+        let layout = layout(&organism.species)?;
+        for component in entity.components.iter_mut() {
+            if component.represents::<Organism>() {
+                dynamic_struct(component.as_mut(), "Organism")?.insert(
+                    "chromosome",
+                    with_enabled_genes(&layout, &organism.chromosome)?,
+                );
+            } else if component.represents::<Uterus>() {
+                // Uterus added by the version 1 migration are complete, and have not mated yet.
+                let Some(uterus) = component.downcast_mut::<DynamicStruct>() else {
+                    continue;
+                };
+                let mate_chromosome = uterus
+                    .field("chromosome")
+                    .and_then(Option::<ga::Chromosome>::from_reflect)
+                    .ok_or("Uterus chromosome is not a chromosome")?;
+                if let Some(mate_chromosome) = mate_chromosome {
+                    uterus.insert(
+                        "chromosome",
+                        Some(with_enabled_genes(&layout, &mate_chromosome)?),
+                    );
+                }
+            }
+        }
+    }
+    let lifecycle = dynamic_resource::<OrganismsLifecycle>(scene)?;
+    if let Some(ReflectMut::List(births)) = lifecycle
+        .field_mut("births")
+        .map(|births| births.reflect_mut())
+    {
+        for i in 0..births.len() {
+            let birth = births.get_mut(i).ok_or("missing OrganismBirth")?;
+            let birth = dynamic_struct(birth, "OrganismBirth")?;
+            let (Some(species), Some(chromosome)) = (
+                birth.field("species").and_then(SpeciesId::from_reflect),
+                birth
+                    .field("chromosome")
+                    .and_then(ga::Chromosome::from_reflect),
+            ) else {
+                return Err("OrganismBirth has no species or chromosome".to_string());
+            };
+            birth.insert(
+                "chromosome",
+                with_enabled_genes(&layout(&species)?, &chromosome)?,
+            );
+        }
+    }
+    Ok(())
+}
+
+// Version 9 chromosome with the enabled flags of the layout added after the weights of each neuron.
+fn with_enabled_genes(
+    layout: &ga::ChromosomeLayout,
+    chromosome: &ga::Chromosome,
+) -> Result<ga::Chromosome, String> {
+    // Organisms without eye have no brain, and an empty chromosome.
+    // Chromosomes rebuilt from organs by the version 8 migration already have the flags.
+    if chromosome.is_empty() || chromosome.len() == layout.len() {
+        return Ok(chromosome.clone());
+    }
+    let mut old_genes = chromosome.iter().copied();
+    let mut weights = HashMap::new();
+    let mut genes = Vec::with_capacity(layout.len());
+    for locus in layout.iter() {
+        let gene = if locus.kind == ga::GeneKind::Boolean && locus.segment() == "brain" {
+            let (neuron, input) = locus
+                .name
+                .rsplit_once(".e")
+                .ok_or_else(|| format!("unexpected brain gene {}", locus.name))?;
+            let weight = weights
+                .get(&format!("{neuron}.w{input}"))
+                .ok_or_else(|| format!("missing weight of {}", locus.name))?;
+            if *weight == 0.0 {
+                0.0
+            } else {
+                1.0
+            }
+        } else {
+            let gene = old_genes
+                .next()
+                .ok_or("chromosome shorter than its layout")?;
+            weights.insert(locus.name.clone(), gene);
+            gene
+        };
+        genes.push(gene);
+    }
+    if old_genes.next().is_some() {
+        return Err("chromosome longer than its layout".to_string());
+    }
+    let new_chromosome = genes.into_iter().collect::<ga::Chromosome>();
+    Ok(match chromosome.neat() {
+        Some(neat) => new_chromosome.with_neat(neat.clone()),
+        None => new_chromosome,
+    })
+}
+
+// Calls f on every accumulated SpeciesStatistic of the EcosystemStatistics.
+fn for_each_species_statistic(
+    scene: &mut DynamicScene,
+    mut f: impl FnMut(&mut DynamicStruct),
+) -> Result<(), String> {
+    let statistics = dynamic_resource::<EcosystemStatistics>(scene)?;
+    let Some(ReflectMut::Map(organisms)) = statistics
+        .field_mut("organisms")
+        .map(|organisms| organisms.reflect_mut())
+    else {
+        return Err("EcosystemStatistics organisms is not a map".to_string());
+    };
+    for i in 0..organisms.len() {
+        let (_, species_statistics) = organisms.get_at_mut(i).ok_or("missing statistics")?;
+        let Some(ReflectMut::List(accumulation)) =
+            dynamic_struct(species_statistics, "SpeciesStatistics")?
+                .field_mut("accumulation")
+                .map(|accumulation| accumulation.reflect_mut())
+        else {
+            return Err("SpeciesStatistics accumulation is not a list".to_string());
+        };
+        for j in 0..accumulation.len() {
+            let step_statistic = accumulation.get_mut(j).ok_or("missing statistic")?;
+            let ReflectMut::Tuple(step_statistic) = step_statistic.reflect_mut() else {
+                return Err("SpeciesStatistics accumulation is not a list of tuples".to_string());
+            };
+            let statistic = step_statistic
+                .field_mut(1)
+                .ok_or("missing SpeciesStatistic")?;
+            f(dynamic_struct(statistic, "SpeciesStatistic")?);
+        }
+    }
+    Ok(())
+}
+
+fn dynamic_resource<T: Reflect + TypePath>(
+    scene: &mut DynamicScene,
+) -> Result<&mut DynamicStruct, String> {
+    scene
+        .resources
+        .iter_mut()
+        .find(|resource| resource.represents::<T>())
+        .and_then(|resource| resource.downcast_mut::<DynamicStruct>())
+        .ok_or_else(|| format!("missing {}", T::short_type_path()))
+}
+
+// Component of the entity, when it is complete.
+fn component<T: FromReflect + TypePath>(components: &[Box<dyn Reflect>]) -> Option<T> {
+    components
+        .iter()
+        .find(|component| component.represents::<T>())
+        .and_then(|component| T::from_reflect(component.as_reflect()))
+}
+
+fn dynamic_struct<'a>(
+    value: &'a mut dyn Reflect,
+    name: &str,
+) -> Result<&'a mut DynamicStruct, String> {
+    value
+        .downcast_mut::<DynamicStruct>()
+        .ok_or_else(|| format!("{name} is not a struct"))
+}
@@ -14,12 +14,92 @@ pub struct SpeciesStatistic {
     // Mean pos X
     pub total_position_x: f32,
     pub total_position_y: f32,
+    // Number of niches of similar chromosomes among the organisms.
+    // Applicable only for organism with GenerationEvolution reproduction and niching
+    #[serde(default)]
+    pub niches: Option<u32>,
+    // Ages of the organisms, in steps.
+    #[serde(default)]
+    pub age_mean: f32,
+    #[serde(default)]
+    pub age_min: u32,
+    #[serde(default)]
+    pub age_max: u32,
+    // Births, deaths and energy since the previous aggregation.
+    #[serde(default, flatten)]
+    pub flows: SpeciesFlows,
+    // Distributions of the evolvable traits, and genetic diversity.
+    #[serde(default, flatten)]
+    pub traits: SpeciesTraits,
+    // Set when older statistics were merged into this one, which then holds their mean.
+    // Only size and energy keep their range in the envelope, and ages their own min and max;
+    // niches, diversity and trait percentiles of merged statistics are means only.
+    #[serde(default)]
+    pub envelope: Option<StatisticEnvelope>,
 }
 impl SpeciesStatistic {
+    // Number of aggregated statistics this one stands for.
+    pub fn samples(&self) -> u32 {
+        self.envelope
+            .as_ref()
+            .map_or(1, |envelope| envelope.samples)
+    }
+    pub fn size_range(&self) -> (u32, u32) {
+        self.envelope
+            .as_ref()
+            .map_or((self.size, self.size), |envelope| {
+                (envelope.size_min, envelope.size_max)
+            })
+    }
+    pub fn energy_total_range(&self) -> (f32, f32) {
+        self.envelope
+            .as_ref()
+            .map_or((self.energy_total, self.energy_total), |envelope| {
+                (envelope.energy_total_min, envelope.energy_total_max)
+            })
+    }
+    // Merges the following statistic into this one.
+    // Values are averaged according to the number of statistics they stand for,
+    // flows are summed, and the lowest and highest size and energy are kept in the envelope.
+    pub fn merge(&mut self, next: SpeciesStatistic) {
+        let (weight, next_weight) = (self.samples() as f32, next.samples() as f32);
+        let mean = |value: f32, next_value: f32| {
+            (value * weight + next_value * next_weight) / (weight + next_weight)
+        };
+        let (size_min, size_max) = self.size_range();
+        let (next_size_min, next_size_max) = next.size_range();
+        let (energy_min, energy_max) = self.energy_total_range();
+        let (next_energy_min, next_energy_max) = next.energy_total_range();
+        self.envelope = Some(StatisticEnvelope {
+            samples: self.samples() + next.samples(),
+            size_min: size_min.min(next_size_min),
+            size_max: size_max.max(next_size_max),
+            energy_total_min: energy_min.min(next_energy_min),
+            energy_total_max: energy_max.max(next_energy_max),
+        });
+        self.generation = next.generation.or(self.generation);
+        self.size = mean(self.size as f32, next.size as f32).round() as u32;
+        self.energy_total = mean(self.energy_total, next.energy_total);
+        self.total_position_x = mean(self.total_position_x, next.total_position_x);
+        self.total_position_y = mean(self.total_position_y, next.total_position_y);
+        self.niches = match (self.niches, next.niches) {
+            (Some(niches), Some(next_niches)) => {
+                Some(mean(niches as f32, next_niches as f32).round() as u32)
+            }
+            (niches, next_niches) => next_niches.or(niches),
+        };
+        self.age_mean = mean(self.age_mean, next.age_mean);
+        self.age_min = self.age_min.min(next.age_min);
+        self.age_max = self.age_max.max(next.age_max);
+        self.flows.add(&next.flows);
+        self.traits.merge(next.traits, &mean);
+    }
     pub fn inline_sprint(&self) -> String {
         format!(
-            "Size:{:5} Energy:{:9.0} Generation:{:-4} Mean_Pos:({:9.3},{:9.3})",
+            "Size:{:5} Births:{:5} Deaths:{:5} Energy:{:9.0} Generation:{:-4} Mean_Pos:({:9.3},{:9.3}){}{}",
             self.size,
+            self.flows.births(),
+            self.flows.deaths(),
             self.energy_total,
             if let Some(generation) = self.generation {
                 generation.to_string()
@@ -28,9 +108,250 @@ impl SpeciesStatistic {
             },
             self.total_position_x / (self.size as f32),
             self.total_position_y / (self.size as f32),
+            if let Some(niches) = self.niches {
+                format!(" Niches:{:3}", niches)
+            } else {
+                String::new()
+            },
+            if let Some(diversity) = self.traits.diversity {
+                format!(" Diversity:{:7.4}", diversity)
+            } else {
+                String::new()
+            },
         )
     }
 }
+// Lowest and highest values of the statistics merged into one.
+// Other values of merged statistics, such as niches and trait percentiles, are not bounded here.
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct StatisticEnvelope {
+    // Number of aggregated statistics merged.
+    pub samples: u32,
+    pub size_min: u32,
+    pub size_max: u32,
+    pub energy_total_min: f32,
+    pub energy_total_max: f32,
+}
+// Counters of a species, accumulated between two aggregations.
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct SpeciesFlows {
+    // Births by origin
+    pub births_minimum_population: u32,
+    pub births_auto_spawn: u32,
+    pub births_generation_evolution: u32,
+    pub births_birth: u32,
+    pub births_import: u32,
+    // Deaths by cause
+    pub deaths_starved: u32,
+    pub deaths_eaten: u32,
+    pub deaths_culled: u32,
+    // Energy gained through the organs
+    pub energy_eaten: f32,
+    pub energy_leaf: f32,
+    // Energy spent by the body and organs
+    pub energy_spent_body: f32,
+    pub energy_spent_brain: f32,
+    pub energy_spent_eye: f32,
+    pub energy_spent_locomotion: f32,
+    pub energy_spent_uterus: f32,
+}
+impl SpeciesFlows {
+    pub fn births(&self) -> u32 {
+        self.births_minimum_population
+            + self.births_auto_spawn
+            + self.births_generation_evolution
+            + self.births_birth
+            + self.births_import
+    }
+    pub fn deaths(&self) -> u32 {
+        self.deaths_starved + self.deaths_eaten + self.deaths_culled
+    }
+    pub fn add_birth(&mut self, origin: BirthOrigin) {
+        *match origin {
+            BirthOrigin::MinimumPopulation => &mut self.births_minimum_population,
+            BirthOrigin::AutoSpawn => &mut self.births_auto_spawn,
+            BirthOrigin::GenerationEvolution => &mut self.births_generation_evolution,
+            BirthOrigin::Birth => &mut self.births_birth,
+            BirthOrigin::Import => &mut self.births_import,
+        } += 1;
+    }
+    pub fn add_death(&mut self, cause: DeathCause) {
+        *match cause {
+            DeathCause::Starved => &mut self.deaths_starved,
+            DeathCause::Eaten => &mut self.deaths_eaten,
+            DeathCause::Culled => &mut self.deaths_culled,
+        } += 1;
+    }
+    pub fn add(&mut self, other: &SpeciesFlows) {
+        self.births_minimum_population += other.births_minimum_population;
+        self.births_auto_spawn += other.births_auto_spawn;
+        self.births_generation_evolution += other.births_generation_evolution;
+        self.births_birth += other.births_birth;
+        self.births_import += other.births_import;
+        self.deaths_starved += other.deaths_starved;
+        self.deaths_eaten += other.deaths_eaten;
+        self.deaths_culled += other.deaths_culled;
+        self.energy_eaten += other.energy_eaten;
+        self.energy_leaf += other.energy_leaf;
+        self.energy_spent_body += other.energy_spent_body;
+        self.energy_spent_brain += other.energy_spent_brain;
+        self.energy_spent_eye += other.energy_spent_eye;
+        self.energy_spent_locomotion += other.energy_spent_locomotion;
+        self.energy_spent_uterus += other.energy_spent_uterus;
+    }
+    pub fn add_energy(&mut self, organ: EnergyOrgan, produced: f32, consumed: f32) {
+        match organ {
+            EnergyOrgan::Mouth => self.energy_eaten += produced,
+            EnergyOrgan::Leaf => self.energy_leaf += produced,
+            EnergyOrgan::Brain => self.energy_spent_brain += consumed,
+            EnergyOrgan::Eye => self.energy_spent_eye += consumed,
+            EnergyOrgan::Locomotion => self.energy_spent_locomotion += consumed,
+            EnergyOrgan::Uterus => self.energy_spent_uterus += consumed,
+        }
+    }
+}
+
+// Distribution of a trait among the organisms of a species.
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct TraitDistribution {
+    pub mean: f32,
+    pub min: f32,
+    pub p10: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub max: f32,
+}
+impl TraitDistribution {
+    // None when there are no values.
+    pub fn from_values(mut values: Vec<f32>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f32::total_cmp);
+        // Nearest rank percentile.
+        let percentile = |p: f32| values[((values.len() - 1) as f32 * p).round() as usize];
+        Some(Self {
+            mean: values.iter().sum::<f32>() / values.len() as f32,
+            min: values[0],
+            p10: percentile(0.1),
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            max: values[values.len() - 1],
+        })
+    }
+    // Percentiles and mean are averaged, as the values they were computed from are not kept.
+    fn merge(&mut self, next: &TraitDistribution, mean: &impl Fn(f32, f32) -> f32) {
+        self.mean = mean(self.mean, next.mean);
+        self.min = self.min.min(next.min);
+        self.p10 = mean(self.p10, next.p10);
+        self.p50 = mean(self.p50, next.p50);
+        self.p90 = mean(self.p90, next.p90);
+        self.max = self.max.max(next.max);
+    }
+}
+
+// Evolvable traits of a species, None for the organs it does not have.
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct SpeciesTraits {
+    pub eye_fov_angle: Option<TraitDistribution>,
+    pub eye_fov_range: Option<TraitDistribution>,
+    pub eye_n_sectors: Option<TraitDistribution>,
+    // Mean and variance of the weights of each brain.
+    pub brain_weight_mean: Option<TraitDistribution>,
+    pub brain_weight_variance: Option<TraitDistribution>,
+    // Mean distance between two chromosomes of the species.
+    // Applicable only for organism with genes
+    pub diversity: Option<f32>,
+}
+impl SpeciesTraits {
+    // Name and distribution of each recorded trait.
+    pub fn distributions(&self) -> [(&'static str, &Option<TraitDistribution>); 5] {
+        [
+            ("Eye fov angle", &self.eye_fov_angle),
+            ("Eye fov range", &self.eye_fov_range),
+            ("Eye sectors", &self.eye_n_sectors),
+            ("Brain weight mean", &self.brain_weight_mean),
+            ("Brain weight variance", &self.brain_weight_variance),
+        ]
+    }
+    fn merge(&mut self, next: SpeciesTraits, mean: &impl Fn(f32, f32) -> f32) {
+        for (distribution, next_distribution) in [
+            (&mut self.eye_fov_angle, next.eye_fov_angle),
+            (&mut self.eye_fov_range, next.eye_fov_range),
+            (&mut self.eye_n_sectors, next.eye_n_sectors),
+            (&mut self.brain_weight_mean, next.brain_weight_mean),
+            (&mut self.brain_weight_variance, next.brain_weight_variance),
+        ] {
+            match (distribution.as_mut(), next_distribution) {
+                (Some(distribution), Some(next_distribution)) => {
+                    distribution.merge(&next_distribution, mean)
+                }
+                (None, next_distribution) => *distribution = next_distribution,
+                (Some(_), None) => (),
+            }
+        }
+        self.diversity = match (self.diversity, next.diversity) {
+            (Some(diversity), Some(next_diversity)) => Some(mean(diversity, next_diversity)),
+            (diversity, next_diversity) => next_diversity.or(diversity),
+        };
+    }
+}
+
+// Maximum number of chromosomes compared for the genetic diversity,
+// as the number of pairs grows with the square of the population.
+const DIVERSITY_SAMPLE_SIZE: usize = 200;
+
+// Mean distance between each pair of chromosomes, on an evenly spaced sample of them.
+fn genetic_diversity(chromosomes: &[&ga::Chromosome]) -> Option<f32> {
+    let stride = chromosomes.len().div_ceil(DIVERSITY_SAMPLE_SIZE).max(1);
+    let sample = chromosomes.iter().step_by(stride).collect::<Vec<_>>();
+    if sample.len() < 2 {
+        return None;
+    }
+    let mut total = 0.0;
+    for (i, a) in sample.iter().enumerate() {
+        for b in sample[i + 1..].iter() {
+            total += a.distance(b);
+        }
+    }
+    let n_pairs = sample.len() * (sample.len() - 1) / 2;
+    Some(total / n_pairs as f32)
+}
+
+// Mean and variance of the weights of a brain network.
+fn brain_weights(network: &BrainNetwork) -> Option<(f32, f32)> {
+    let weights = match network {
+        BrainNetwork::Layered(network) => network.weights().collect::<Vec<_>>(),
+        BrainNetwork::Neat(network) => network
+            .connections()
+            .iter()
+            .filter(|connection| connection.enabled)
+            .map(|connection| connection.weight)
+            .collect(),
+    };
+    if weights.is_empty() {
+        return None;
+    }
+    let n = weights.len() as f32;
+    let mean = weights.iter().sum::<f32>() / n;
+    let variance = weights.iter().map(|w| (w - mean).powi(2)).sum::<f32>() / n;
+    Some((mean, variance))
+}
+
+// Flows of each species since the previous aggregation.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct StatisticsFlows {
+    pub per_species: BTreeMap<SpeciesId, SpeciesFlows>,
+}
+impl StatisticsFlows {
+    pub fn get_mut(&mut self, species: SpeciesId) -> &mut SpeciesFlows {
+        self.per_species.entry(species).or_default()
+    }
+}
+
 #[derive(Reflect, Serialize, Deserialize, Debug)]
 pub struct SpeciesStatistics {
     pub name: String,
@@ -50,8 +371,35 @@ impl SpeciesStatistics {
             String::new()
         }
     }
-    pub fn add(&mut self, step: u32, stat: SpeciesStatistic) {
+    // Adds the statistic of the step, downsampling the older ones to stay within max_length.
+    pub fn add(&mut self, step: u32, stat: SpeciesStatistic, max_length: usize) {
         self.accumulation.push((step, stat));
+        if self.accumulation.len() > max_length {
+            self.downsample();
+        }
+    }
+    // The most recent third is kept as is, the older statistics are merged two by two,
+    // each merged one at the mean step of the statistics it stands for, rounded to a step,
+    // so that it is plotted at the middle of the steps it covers.
+    // Repeated, older statistics get an ever lower resolution.
+    fn downsample(&mut self) {
+        let n_older = self.accumulation.len() - self.accumulation.len() / 3;
+        let recent = self.accumulation.split_off(n_older);
+        let mut older = std::mem::take(&mut self.accumulation).into_iter();
+        while let Some((step, mut stat)) = older.next() {
+            let Some((next_step, next)) = older.next() else {
+                self.accumulation.push((step, stat));
+                continue;
+            };
+            let (weight, next_weight) = (stat.samples() as u64, next.samples() as u64);
+            let total_weight = weight + next_weight;
+            let mean_step =
+                (step as u64 * weight + next_step as u64 * next_weight + total_weight / 2)
+                    / total_weight;
+            stat.merge(next);
+            self.accumulation.push((mean_step as u32, stat));
+        }
+        self.accumulation.extend(recent);
     }
     pub fn last(&self) -> Option<&SpeciesStatistic> {
         self.accumulation.last().map(|(_, stat)| stat)
@@ -62,6 +410,9 @@ impl SpeciesStatistics {
 #[reflect(Resource)]
 pub struct EcosystemStatistics {
     pub organisms: BTreeMap<SpeciesId, SpeciesStatistics>,
+    // Steps at which config changes were applied to the running ecosystem, with the changes.
+    #[serde(default)]
+    pub config_changes: Vec<(u32, Vec<String>)>,
 }
 
 impl EcosystemStatistics {
@@ -73,7 +424,18 @@ impl EcosystemStatistics {
                 SpeciesStatistics::new(species_config.name.clone()),
             );
         }
-        Self { organisms }
+        Self {
+            organisms,
+            config_changes: vec![],
+        }
+    }
+    // Records the config changes applied at the step, dropping the oldest ones beyond max_length.
+    pub fn add_config_changes(&mut self, step: u32, changes: Vec<String>, max_length: usize) {
+        self.config_changes.push((step, changes));
+        if self.config_changes.len() > max_length {
+            let n_dropped = self.config_changes.len() - max_length;
+            self.config_changes.drain(..n_dropped);
+        }
     }
     pub fn sprint(&self, cur_step: u32) -> String {
         let mut s = String::new();
@@ -85,29 +447,51 @@ impl EcosystemStatistics {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn statistics_accumulation(
     ecosystem_statistics: ResMut<EcosystemStatistics>,
+    statistics_sinks: ResMut<StatisticsSinks>,
+    statistics_flows: ResMut<StatisticsFlows>,
     ecosystem_runtime: Res<EcosystemRuntime>,
     config: Res<EcosystemConfig>,
+    generation_evolutions: Res<GenerationEvolutions>,
     organisms: Query<(&Organism, &Body, &Position)>,
+    organs: Query<(&Organism, Option<&Eye>, Option<&Brain>)>,
 ) {
     if ecosystem_runtime.steps >= config.statistics_aggregation_start
-        && ecosystem_runtime.steps % config.statistics_aggregation_rate == 0
+        && ecosystem_runtime
+            .steps
+            .is_multiple_of(config.statistics_aggregation_rate)
     {
         // Update current statistics
-        accumulate_statistics(ecosystem_statistics, ecosystem_runtime, organisms);
+        accumulate_statistics(
+            ecosystem_statistics,
+            statistics_sinks,
+            statistics_flows,
+            ecosystem_runtime,
+            config,
+            generation_evolutions,
+            organisms,
+            organs,
+        );
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn accumulate_statistics(
     mut ecosystem_statistics: ResMut<EcosystemStatistics>,
+    mut statistics_sinks: ResMut<StatisticsSinks>,
+    mut statistics_flows: ResMut<StatisticsFlows>,
     ecosystem_runtime: Res<EcosystemRuntime>,
+    config: Res<EcosystemConfig>,
+    generation_evolutions: Res<GenerationEvolutions>,
     organisms: Query<(&Organism, &Body, &Position)>,
+    organs: Query<(&Organism, Option<&Eye>, Option<&Brain>)>,
 ) {
     let mut current_stats = BTreeMap::new();
     for (species, generation) in ecosystem_runtime.generation.iter() {
         current_stats.insert(
-            species,
+            *species,
             SpeciesStatistic {
                 generation: Some(*generation),
                 ..Default::default()
@@ -122,6 +506,44 @@ pub fn accumulate_statistics(
         stat.energy_total += body.energy;
         stat.total_position_x += position.x;
         stat.total_position_y += position.y;
+        stat.age_mean += organism.age as f32;
+        stat.age_min = if stat.size == 1 {
+            organism.age
+        } else {
+            stat.age_min.min(organism.age)
+        };
+        stat.age_max = stat.age_max.max(organism.age);
+    }
+    for (species, stat) in current_stats.iter_mut() {
+        stat.age_mean /= stat.size.max(1) as f32;
+        stat.flows = statistics_flows
+            .per_species
+            .remove(species)
+            .unwrap_or_default();
+    }
+    for (species, state) in generation_evolutions.per_species.iter() {
+        if let Some(niching) = state.genetic_algorithm.niching() {
+            let chromosomes = organisms
+                .iter()
+                .filter(|(organism, _, _)| &organism.species == species)
+                .map(|(organism, _, _)| &organism.chromosome)
+                .collect::<Vec<_>>();
+            let n_niches = niching
+                .niches(&chromosomes)
+                .into_iter()
+                .max()
+                .map_or(0, |max| max + 1);
+            if let Some(stat) = current_stats.get_mut(species) {
+                stat.niches = Some(n_niches as u32);
+            }
+        }
+    }
+    for (species, stat) in current_stats.iter_mut() {
+        stat.traits = species_traits(
+            organs
+                .iter()
+                .filter(|(organism, _, _)| &organism.species == species),
+        );
     }
     for (species, stats) in ecosystem_statistics.organisms.iter_mut() {
         stats.add(
@@ -129,8 +551,76 @@ pub fn accumulate_statistics(
             current_stats
                 .remove(species)
                 .expect("EcosystemStatistics has a SpeciesId unknown to EcosystemRuntime"),
+            config.statistics_max_length as usize,
         );
     }
-    // Print in console
-    println!("{}", ecosystem_statistics.sprint(ecosystem_runtime.steps));
+    statistics_sinks.write(ecosystem_runtime.steps, &ecosystem_statistics);
+}
+
+fn species_traits<'a>(
+    organisms: impl Iterator<Item = (&'a Organism, Option<&'a Eye>, Option<&'a Brain>)>,
+) -> SpeciesTraits {
+    let mut fov_angles = vec![];
+    let mut fov_ranges = vec![];
+    let mut n_sectors = vec![];
+    let mut weight_means = vec![];
+    let mut weight_variances = vec![];
+    let mut chromosomes = vec![];
+    for (organism, eye, brain) in organisms {
+        if let Some(eye) = eye {
+            fov_angles.push(eye.fov_angle);
+            fov_ranges.push(eye.fov_range);
+            n_sectors.push(eye.n_sectors as f32);
+        }
+        if let Some((mean, variance)) = brain.and_then(|brain| brain_weights(&brain.nn)) {
+            weight_means.push(mean);
+            weight_variances.push(variance);
+        }
+        if !organism.chromosome.is_empty() {
+            chromosomes.push(&organism.chromosome);
+        }
+    }
+    SpeciesTraits {
+        eye_fov_angle: TraitDistribution::from_values(fov_angles),
+        eye_fov_range: TraitDistribution::from_values(fov_ranges),
+        eye_n_sectors: TraitDistribution::from_values(n_sectors),
+        brain_weight_mean: TraitDistribution::from_values(weight_means),
+        brain_weight_variance: TraitDistribution::from_values(weight_variances),
+        diversity: genetic_diversity(&chromosomes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsampling() {
+        let mut statistics = SpeciesStatistics::new("A".to_string());
+        for step in 0..10 {
+            let stat = SpeciesStatistic {
+                size: step,
+                ..Default::default()
+            };
+            statistics.add(step, stat, 9);
+        }
+        // At 10 statistics, the 7 older are merged into 4, the 3 recent are kept.
+        // Merged statistics are at the middle of the steps they cover, rounded up.
+        let steps = statistics
+            .accumulation
+            .iter()
+            .map(|(step, _)| *step)
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec![1, 3, 5, 6, 7, 8, 9]);
+        let (_, merged) = &statistics.accumulation[1];
+        assert_eq!((merged.samples(), merged.size_range()), (2, (2, 3)));
+        let (_, single) = &statistics.accumulation[3];
+        assert_eq!((single.samples(), single.size_range()), (1, (6, 6)));
+        // Merging again weighs each step by the number of statistics it stands for.
+        for step in 10..13 {
+            statistics.add(step, SpeciesStatistic::default(), 9);
+        }
+        let (step, merged) = &statistics.accumulation[0];
+        assert_eq!((*step, merged.samples()), (2, 4));
+    }
 }
@@ -0,0 +1,253 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::ecosystem::*;
+
+// Destination of the statistics, written at each aggregation step.
+pub trait StatisticsSink: Send + Sync {
+    // Writes the statistics of the given step, the last accumulated ones.
+    fn write(&mut self, step: u32, statistics: &EcosystemStatistics) -> io::Result<()>;
+}
+
+// Human readable summary, printed on the standard output.
+pub struct ConsoleSink;
+impl StatisticsSink for ConsoleSink {
+    fn write(&mut self, step: u32, statistics: &EcosystemStatistics) -> io::Result<()> {
+        println!("{}", statistics.sprint(step));
+        Ok(())
+    }
+}
+
+// One row per species per aggregation step.
+#[derive(Serialize)]
+struct StatisticsRow<'a> {
+    step: u32,
+    species: &'a str,
+    #[serde(flatten)]
+    statistic: &'a SpeciesStatistic,
+}
+
+type Row = serde_json::Map<String, serde_json::Value>;
+
+fn rows(
+    step: u32,
+    statistics: &EcosystemStatistics,
+) -> impl Iterator<Item = serde_json::Result<Row>> + '_ {
+    statistics
+        .organisms
+        .values()
+        .filter_map(move |species| {
+            species.last().map(|statistic| StatisticsRow {
+                step,
+                species: &species.name,
+                statistic,
+            })
+        })
+        .map(row_object)
+}
+
+fn row_object(row: StatisticsRow) -> serde_json::Result<Row> {
+    match serde_json::to_value(row)? {
+        serde_json::Value::Object(map) => Ok(map),
+        _ => unreachable!("a struct is serialized as an object"),
+    }
+}
+
+// Nested objects are flattened into columns prefixed by their field name.
+// ex: eye_fov_angle_p50, envelope_size_min
+fn flatten(row: Row) -> Row {
+    let mut columns = Row::new();
+    for (name, value) in row {
+        flatten_into(name, value, &mut columns);
+    }
+    columns
+}
+
+fn flatten_into(name: String, value: serde_json::Value, columns: &mut Row) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (field, value) in fields {
+                flatten_into(format!("{name}_{field}"), value, columns);
+            }
+        }
+        value => {
+            columns.insert(name, value);
+        }
+    }
+}
+
+// Names of all the columns, including the ones of the distributions and envelope
+// that are not set for every species or step.
+fn csv_columns() -> serde_json::Result<Vec<String>> {
+    let distribution = Some(TraitDistribution::default());
+    let statistic = SpeciesStatistic {
+        traits: SpeciesTraits {
+            eye_fov_angle: distribution.clone(),
+            eye_fov_range: distribution.clone(),
+            eye_n_sectors: distribution.clone(),
+            brain_weight_mean: distribution.clone(),
+            brain_weight_variance: distribution,
+            diversity: Some(0.0),
+        },
+        envelope: Some(StatisticEnvelope::default()),
+        ..Default::default()
+    };
+    let row = row_object(StatisticsRow {
+        step: 0,
+        species: "",
+        statistic: &statistic,
+    })?;
+    Ok(flatten(row).into_iter().map(|(name, _)| name).collect())
+}
+
+// Comma separated values, with a header row of the statistics names.
+pub struct CsvSink {
+    writer: BufWriter<File>,
+    // Set once the header is written.
+    columns: Option<Vec<String>>,
+}
+impl CsvSink {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            columns: None,
+        })
+    }
+}
+impl StatisticsSink for CsvSink {
+    fn write(&mut self, step: u32, statistics: &EcosystemStatistics) -> io::Result<()> {
+        if self.columns.is_none() {
+            let columns = csv_columns()?;
+            let header = columns
+                .iter()
+                .map(|name| csv_field(name))
+                .collect::<Vec<_>>();
+            writeln!(self.writer, "{}", header.join(","))?;
+            self.columns = Some(columns);
+        }
+        let columns = self.columns.as_ref().expect("header written");
+        for row in rows(step, statistics) {
+            let row = flatten(row?);
+            let values = columns
+                .iter()
+                .map(|name| match row.get(name) {
+                    None | Some(serde_json::Value::Null) => String::new(),
+                    Some(serde_json::Value::String(s)) => csv_field(s),
+                    Some(value) => csv_field(&value.to_string()),
+                })
+                .collect::<Vec<_>>();
+            writeln!(self.writer, "{}", values.join(","))?;
+        }
+        // Flushed at each step, so that the file can be read while running.
+        self.writer.flush()
+    }
+}
+
+// Quotes the field when it contains a separator or a quote.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// One JSON object per line.
+pub struct JsonLinesSink {
+    writer: BufWriter<File>,
+}
+impl JsonLinesSink {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+}
+impl StatisticsSink for JsonLinesSink {
+    fn write(&mut self, step: u32, statistics: &EcosystemStatistics) -> io::Result<()> {
+        for row in rows(step, statistics) {
+            serde_json::to_writer(&mut self.writer, &row?)?;
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()
+    }
+}
+
+// Creates the sink matching the path extension: '.csv' for CSV and '.jsonl' for JSON Lines.
+pub fn statistics_file_sink(path: &Path) -> io::Result<Box<dyn StatisticsSink>> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => Ok(Box::new(CsvSink::create(path)?)),
+        Some("jsonl") => Ok(Box::new(JsonLinesSink::create(path)?)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown statistics format for {path:?}, expected .csv or .jsonl"),
+        )),
+    }
+}
+
+// All the destinations of the statistics.
+#[derive(Resource)]
+pub struct StatisticsSinks {
+    pub sinks: Vec<Box<dyn StatisticsSink>>,
+}
+impl StatisticsSinks {
+    pub fn write(&mut self, step: u32, statistics: &EcosystemStatistics) {
+        for sink in self.sinks.iter_mut() {
+            if let Err(err) = sink.write(step, statistics) {
+                error!("Statistics could not be written: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_columns_are_flat() {
+        let path = std::env::temp_dir().join(format!("exelixi_stats_{}.csv", std::process::id()));
+        let mut statistics = EcosystemStatistics::default();
+        let species = statistics
+            .organisms
+            .entry(SpeciesId::new(0))
+            .or_insert_with(|| SpeciesStatistics::new("Herbivore".to_string()));
+        let mut sink = CsvSink::create(&path).unwrap();
+        species.add(0, SpeciesStatistic::default(), 10);
+        sink.write(0, &statistics).unwrap();
+        let mut statistic = SpeciesStatistic {
+            size: 3,
+            ..Default::default()
+        };
+        statistic.traits.eye_fov_angle = Some(TraitDistribution {
+            p50: 1.5,
+            ..Default::default()
+        });
+        statistics
+            .organisms
+            .get_mut(&SpeciesId::new(0))
+            .unwrap()
+            .add(10, statistic, 10);
+        sink.write(10, &statistics).unwrap();
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines = csv
+            .lines()
+            .map(|line| line.split(',').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        let header = &lines[0];
+        let column = |name: &str| header.iter().position(|column| *column == name).unwrap();
+        assert!(!header.contains(&"eye_fov_angle"));
+        assert!(header.contains(&"envelope_size_min"));
+        assert!(lines.iter().all(|line| line.len() == header.len()));
+        assert_eq!(lines[1][column("step")], "0");
+        assert_eq!(lines[1][column("species")], "Herbivore");
+        assert_eq!(lines[1][column("eye_fov_angle_p50")], "");
+        assert_eq!(lines[2][column("step")], "10");
+        assert_eq!(lines[2][column("size")], "3");
+        assert_eq!(lines[2][column("eye_fov_angle_p50")], "1.5");
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use bevy::reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer};
+use bevy::reflect::{FromReflect, TypePath, TypeRegistry};
+use lib_genetic_algorithm::{Chromosome, Individual, MutationMethod};
+use serde::de::DeserializeSeed;
+
+use crate::ecosystem::*;
+
+// A single organism, exported from a simulation to be imported into another one.
+#[derive(Reflect)]
+pub struct OrganismExport {
+    // Configuration of the species the organism was exported from.
+    pub species: SpeciesConfig,
+    pub chromosome: Chromosome,
+    // Organs decoded from the chromosome, for information only.
+    pub eye: Option<Eye>,
+    pub brain: Option<Brain>,
+}
+
+// Chromosomes of the organisms of a species, to seed a species of another simulation.
+#[derive(Reflect)]
+pub struct GenePool {
+    // Name of the species the chromosomes were taken from.
+    pub species: String,
+    pub chromosomes: Vec<Chromosome>,
+}
+
+// Chromosomes the random organisms of each species are sampled from.
+#[derive(Resource, Default)]
+pub struct GenePools {
+    pub per_species: BTreeMap<SpeciesId, Vec<Chromosome>>,
+}
+impl GenePools {
+    // Loads the gene pools of the config, keeping only the chromosomes compatible with their species.
+    // A species whose gene pool cannot be read spawns random organisms.
+    pub fn new(config: &EcosystemConfig, type_registry: &TypeRegistry) -> Self {
+        let mut per_species = BTreeMap::new();
+        for species in config.species.values() {
+            let Some(gene_pool_config) = &species.gene_pool else {
+                continue;
+            };
+            match read_ron::<GenePool>(Path::new(&gene_pool_config.path), type_registry) {
+                Ok(gene_pool) => {
+                    let chromosomes = gene_pool
+                        .chromosomes
+                        .into_iter()
+                        .filter(|chromosome| {
+                            OrganismIndividual::check_chromosome(species, chromosome, None)
+                                .map_err(|err| {
+                                    warn!(
+                                        "Gene pool chromosome skipped for {}: {err}",
+                                        species.name
+                                    )
+                                })
+                                .is_ok()
+                        })
+                        .collect::<Vec<_>>();
+                    info!(
+                        "Species {} seeded from {} chromosomes of '{}'",
+                        species.name,
+                        chromosomes.len(),
+                        gene_pool_config.path
+                    );
+                    per_species.insert(species.id, chromosomes);
+                }
+                Err(err) => error!(
+                    "Gene pool of species {} could not be loaded from '{}': {err}",
+                    species.name, gene_pool_config.path
+                ),
+            }
+        }
+        Self { per_species }
+    }
+    // Chromosome sampled from the species gene pool, and mutated, None without gene pool.
+    pub fn sample(&self, rng: &mut dyn RngCore, config: &SpeciesConfig) -> Option<Chromosome> {
+        let gene_pool_config = config.gene_pool.as_ref()?;
+        let chromosomes = self.per_species.get(&config.id)?;
+        if chromosomes.is_empty() {
+            return None;
+        }
+        let mut chromosome = chromosomes[rng.gen_range(0..chromosomes.len())].clone();
+        if gene_pool_config.mutation_chance > 0.0 {
+            // Only the gene values change, sampled organisms keep the brain topology of the pool.
+            ga::KeepDisabled::new(
+                ga::GaussianMutation::new(
+                    gene_pool_config.mutation_chance,
+                    gene_pool_config.mutation_amplitude,
+                ),
+                OrganismIndividual::brain_connection_loci(config),
+            )
+            .mutate(rng, &mut chromosome);
+            config.chromosome_layout.repair(&mut chromosome);
+        }
+        Some(chromosome)
+    }
+}
+
+// Writes the chromosomes of all the organisms of the species to a RON file.
+// Organisms without chromosome, such as plants, are left out.
+pub fn export_gene_pool(
+    world: &mut World,
+    species_name: &str,
+    path: &Path,
+) -> Result<(), SaveError> {
+    let species = species_id(world, species_name)?;
+    let (chromosomes, empty): (Vec<_>, Vec<_>) = world
+        .query::<&Organism>()
+        .iter(world)
+        .filter(|organism| organism.species == species)
+        .map(|organism| organism.chromosome.clone())
+        .partition(|chromosome| !chromosome.is_empty());
+    let n_chromosomes = chromosomes.len();
+    let gene_pool = GenePool {
+        species: species_name.to_string(),
+        chromosomes,
+    };
+    write_ron(
+        &gene_pool,
+        path,
+        &world.resource::<AppTypeRegistry>().read(),
+    )?;
+    info!("Gene pool of {n_chromosomes} {species_name} exported to '{path:?}'");
+    if !empty.is_empty() {
+        warn!(
+            "{} {species_name} without chromosome left out of the gene pool",
+            empty.len()
+        );
+    }
+    Ok(())
+}
+
+// Writes the organism to a RON file.
+pub fn export_organism(world: &World, entity: Entity, path: &Path) -> Result<(), SaveError> {
+    let organism = world
+        .get::<Organism>(entity)
+        .ok_or_else(|| SaveError::Serialization(format!("{entity:?} is not an organism")))?;
+    let species = world.resource::<EcosystemConfig>().species[&organism.species].clone();
+    let chromosome = organism.chromosome.clone();
+    // Organisms without chromosome, such as plants, could not be born again from it.
+    if chromosome.is_empty() {
+        return Err(SaveError::Serialization(format!(
+            "{entity:?} has no chromosome to export"
+        )));
+    }
+    // Organs are decoded again from the chromosome, as components cannot be cloned.
+    let (_, eye, _, brain) =
+        OrganismIndividual::create(chromosome.clone()).into_components(&species);
+    let export = OrganismExport {
+        species,
+        chromosome,
+        eye,
+        brain: Some(brain),
+    };
+    write_ron(&export, path, &world.resource::<AppTypeRegistry>().read())?;
+    info!(
+        "Organism {entity:?} of species {} exported to '{path:?}'",
+        export.species.name
+    );
+    Ok(())
+}
+
+// Reads an exported organism and adds it to the births of the ecosystem.
+// It is born in the species of the given name, or of the name it was exported from.
+pub fn import_organism(
+    world: &mut World,
+    path: &Path,
+    species_name: Option<&str>,
+) -> Result<SpeciesId, SaveError> {
+    let export: OrganismExport = read_ron(path, &world.resource::<AppTypeRegistry>().read())?;
+    let species_name = species_name.unwrap_or(&export.species.name);
+    let species = species_id(world, species_name)?;
+    OrganismIndividual::check_chromosome(
+        &world.resource::<EcosystemConfig>().species[&species],
+        &export.chromosome,
+        Some(&export.species.chromosome_layout),
+    )
+    .map_err(SaveError::IncompatibleOrganism)?;
+    world
+        .resource_mut::<OrganismsLifecycle>()
+        .births
+        .push(OrganismBirth {
+            species,
+            position: None,
+            energy: None,
+            chromosome: export.chromosome,
+            origin: BirthOrigin::Import,
+            parents: vec![],
+        });
+    info!("Organism from '{path:?}' imported into species {species_name}");
+    Ok(species)
+}
+
+// Organism of the species with the most energy.
+pub fn champion_organism(world: &mut World, species_name: &str) -> Option<Entity> {
+    let species = species_id(world, species_name).ok()?;
+    world
+        .query::<(Entity, &Organism, &Body)>()
+        .iter(world)
+        .filter(|(_, organism, _)| organism.species == species)
+        .max_by(|(_, _, b1), (_, _, b2)| b1.energy.total_cmp(&b2.energy))
+        .map(|(entity, _, _)| entity)
+}
+
+fn species_id(world: &World, species_name: &str) -> Result<SpeciesId, SaveError> {
+    world
+        .resource::<EcosystemConfig>()
+        .species
+        .values()
+        .find(|species| species.name == species_name)
+        .map(|species| species.id)
+        .ok_or_else(|| SaveError::IncompatibleOrganism(format!("no species named {species_name}")))
+}
+
+fn write_ron<T: Reflect>(
+    value: &T,
+    path: &Path,
+    type_registry: &TypeRegistry,
+) -> Result<(), SaveError> {
+    let data = ron::ser::to_string_pretty(
+        &TypedReflectSerializer::new(value, type_registry),
+        ron::ser::PrettyConfig::default(),
+    )
+    .map_err(|err| SaveError::Serialization(err.to_string()))?;
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+fn read_ron<T: FromReflect + TypePath>(
+    path: &Path,
+    type_registry: &TypeRegistry,
+) -> Result<T, SaveError> {
+    let data = std::fs::read(path)?;
+    let registration = type_registry
+        .get(std::any::TypeId::of::<T>())
+        .unwrap_or_else(|| panic!("{} is not registered", T::type_path()));
+    let mut deserializer = ron::de::Deserializer::from_bytes(&data)
+        .map_err(|err| SaveError::Deserialization(err.to_string()))?;
+    let value = TypedReflectDeserializer::new(registration, type_registry)
+        .deserialize(&mut deserializer)
+        .map_err(|err| SaveError::Deserialization(err.to_string()))?;
+    T::from_reflect(value.as_ref()).ok_or_else(|| {
+        SaveError::Deserialization(format!("content is not a {}", T::short_type_path()))
+    })
+}
@@ -19,7 +19,7 @@ mod prelude {
 
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use log::LogPlugin;
 use prelude::*;
 
@@ -27,9 +27,15 @@ use prelude::*;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Path to the simulation config to use
     #[arg(long)]
     config: Option<PathBuf>,
+    /// Apply the changes of the config file while running, such as spawn rates, costs, mutation rates and environment size.
+    /// Changes to the species, their organs or their genes are rejected.
+    #[arg(long, requires = "config")]
+    watch_config: bool,
     /// Initial seed for the simulation, randomized when not provided.
     #[arg(long)]
     seed: Option<u64>,
@@ -40,9 +46,45 @@ struct Args {
     /// Path to load a saved simulation state from
     #[arg(long)]
     load: Option<PathBuf>,
+    /// Path of a save, or of a checkpoint directory to take the latest checkpoint from,
+    /// to continue exactly as the saved simulation would have, with its config and random state.
+    #[arg(long, conflicts_with_all = ["config", "seed", "stats_start", "stats_rate", "load"])]
+    resume: Option<PathBuf>,
     /// Default path to save the simulation to
     #[arg(long)]
     save: Option<PathBuf>,
+    /// Encoding of the saves, deduced from the save path extension when not provided:
+    /// '.bin' for binary, '.zst' for compressed, ron otherwise.
+    #[arg(long, value_enum)]
+    save_format: Option<SaveFormat>,
+    /// Directory to periodically save the simulation to, with the statistics of each save.
+    #[arg(long)]
+    checkpoint_dir: Option<PathBuf>,
+    /// Number of steps between two checkpoints
+    #[arg(long, default_value_t = 10000, requires = "checkpoint_dir")]
+    checkpoint_every: u32,
+    /// Number of most recent checkpoints to keep
+    #[arg(long, default_value_t = 3, requires = "checkpoint_dir")]
+    checkpoint_keep_last: usize,
+    /// When set, every Mth checkpoint is also kept
+    #[arg(long, value_name = "M", requires = "checkpoint_dir")]
+    checkpoint_keep_every: Option<u32>,
+    /// Organism file to import at start, can be repeated
+    #[arg(long)]
+    import_organism: Vec<PathBuf>,
+    /// Species the organisms are imported into, the species they were exported from by default
+    #[arg(long, requires = "import_organism")]
+    import_species: Option<String>,
+    /// Path to export the organism with the most energy of the --export-species to, at the end of the run
+    #[arg(long, requires = "export_species")]
+    export_organism: Option<PathBuf>,
+    /// Path to export the chromosomes of all the organisms of the --export-species to, at the end of the run.
+    /// It can then seed a species through its gene_pool config.
+    #[arg(long, requires = "export_species")]
+    export_gene_pool: Option<PathBuf>,
+    /// Species of the exported organism or gene pool
+    #[arg(long)]
+    export_species: Option<String>,
     /// When set, no GUI is open and program exist after doing the possible load/run_for/save commands.
     #[arg(long)]
     exit: bool,
@@ -52,10 +94,120 @@ struct Args {
     /// When defined override configuration statistics_aggregation_rate value
     #[arg(long)]
     stats_rate: Option<u32>,
+    /// File to write the statistics to, one row per species per aggregation step, can be repeated.
+    /// '.csv' for CSV, '.jsonl' for JSON Lines.
+    #[arg(long)]
+    stats_output: Vec<PathBuf>,
+    /// Do not print the statistics on the standard output
+    #[arg(long)]
+    no_stats_console: bool,
+    /// File to write the births, with their parents, and the deaths of the organisms to, as JSON Lines.
+    /// It can then be read by the lineage command.
+    #[arg(long)]
+    lineage_output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the content of a save without running it: population, energy and genes of each species, and config
+    Inspect {
+        /// Path of the save to inspect
+        save: PathBuf,
+        /// Path to dump the organisms of the save to
+        #[arg(long)]
+        organisms: Option<PathBuf>,
+        /// Format of the organisms dump, deduced from its extension when not provided:
+        /// '.json' for json, csv otherwise.
+        #[arg(long, value_enum, requires = "organisms")]
+        organisms_format: Option<OrganismsFormat>,
+    },
+    /// Check a config, reporting all its errors and warnings
+    CheckConfig {
+        /// Path of the config to check
+        config: PathBuf,
+    },
+    /// Print the lineages of a --lineage-output log: surviving lineages and common ancestor of each species
+    Lineage {
+        /// Path of the lineage log
+        log: PathBuf,
+        /// Only consider the organisms of this species
+        #[arg(long)]
+        species: Option<String>,
+        /// Path to export the ancestry of the living organisms to
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Format of the export, deduced from its extension when not provided:
+        /// '.graphml' for GraphML with all the parents, Newick following the first parent otherwise.
+        #[arg(long, value_enum, requires = "output")]
+        format: Option<PhylogenyFormat>,
+        /// Export all the organisms of the log, not only the living ones and their ancestors
+        #[arg(long, requires = "output")]
+        all: bool,
+    },
 }
 
 fn main() {
     let args = Args::parse();
+    match &args.command {
+        Some(Command::Inspect {
+            save,
+            organisms,
+            organisms_format,
+        }) => {
+            let organisms = organisms.as_deref().map(|path| {
+                (
+                    path,
+                    organisms_format.unwrap_or_else(|| OrganismsFormat::from_path(path)),
+                )
+            });
+            if let Err(err) = inspect_save(save, organisms) {
+                eprintln!("Save '{save:?}' could not be inspected: {err}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::CheckConfig { config }) => {
+            match check_config_file(config) {
+                Ok(report) => {
+                    print!("{report}");
+                    let n_errors = report.errors().count();
+                    let n_warnings = report.warnings().count();
+                    println!("{config:?}: {n_errors} errors, {n_warnings} warnings");
+                    if n_errors > 0 {
+                        std::process::exit(1);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Config '{config:?}' could not be read: {err}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Lineage {
+            log,
+            species,
+            output,
+            format,
+            all,
+        }) => {
+            let output = output.as_deref().map(|path| {
+                (
+                    path,
+                    format.unwrap_or_else(|| PhylogenyFormat::from_path(path)),
+                )
+            });
+            if let Err(err) = export_lineage(log, species.as_deref(), output, *all) {
+                eprintln!("Lineage log '{log:?}' could not be exported: {err}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => (),
+    }
+    if args.load.is_some() && (args.config.is_some() || args.seed.is_some()) {
+        eprintln!("Warning: --config and --seed are replaced by the ones of the loaded save, use --resume to continue a save.");
+    }
     // Handle command line argument overrides
     let mut app = App::new();
     if !args.exit {
@@ -65,14 +217,28 @@ fn main() {
     }
     app.add_plugins(ecosystem::EcosystemPlugin {
         seed: args.seed,
-        config_path: args.config,
+        config_path: args.config.clone(),
         override_stats_start: args.stats_start,
         override_stats_rate: args.stats_rate,
+        statistics_outputs: args.stats_output,
+        statistics_console: !args.no_stats_console,
+        lineage_output: args.lineage_output,
     });
     app.add_plugins(simulation::SimulationPlugin {
-        load_path: args.load,
+        load_path: args.load.or(args.resume.clone()),
+        resume: args.resume.is_some(),
         run_for: args.run_for,
         save_path: args.save,
+        save_format: args.save_format,
+        checkpoint_directory: args.checkpoint_dir,
+        checkpoint_every: args.checkpoint_every,
+        checkpoint_keep_last: args.checkpoint_keep_last,
+        checkpoint_keep_every: args.checkpoint_keep_every,
+        import_organisms: args.import_organism,
+        import_species: args.import_species,
+        export_organism: args.export_organism.zip(args.export_species.clone()),
+        export_gene_pool: args.export_gene_pool.zip(args.export_species),
+        watch_config: args.config.filter(|_| args.watch_config),
         exit: args.exit,
     });
     app.run();
@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+// Periodic saves of the ecosystem into a directory, with their statistics.
+pub struct Checkpointing {
+    pub directory: PathBuf,
+    // Number of steps between two checkpoints.
+    pub every: u32,
+    pub format: SaveFormat,
+    // Number of most recent checkpoints to keep.
+    pub keep_last: usize,
+    // When set, every keep_every-th checkpoint is kept as well.
+    pub keep_every: Option<u32>,
+    // Steps of the checkpoints written and not yet removed, oldest first.
+    written: Vec<u32>,
+}
+impl Checkpointing {
+    pub fn new(
+        directory: PathBuf,
+        every: u32,
+        format: SaveFormat,
+        keep_last: usize,
+        keep_every: Option<u32>,
+    ) -> Self {
+        assert!(every > 0);
+        Self {
+            directory,
+            every,
+            format,
+            keep_last,
+            keep_every,
+            written: vec![],
+        }
+    }
+    // Snapshot path for the given step, the statistics sidecar shares its stem.
+    pub fn snapshot_path(&self, step: u32) -> PathBuf {
+        self.directory
+            .join(format!("step_{step:010}.{}", self.format.extension()))
+    }
+    pub fn stats_path(&self, step: u32) -> PathBuf {
+        self.directory.join(format!("step_{step:010}.stats"))
+    }
+    // Step of the path, when it is a snapshot of this format.
+    fn snapshot_step(&self, path: &Path) -> Option<u32> {
+        path.file_name()?
+            .to_str()?
+            .strip_prefix("step_")?
+            .strip_suffix(&format!(".{}", self.format.extension()))?
+            .parse()
+            .ok()
+    }
+    // Takes back the checkpoints already in the directory, when resuming from one of them,
+    // so that the retention also applies to the ones written before the resume.
+    pub fn rebuild_written(&mut self) -> std::io::Result<()> {
+        let mut written = vec![];
+        if !self.directory.exists() {
+            self.written = written;
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(&self.directory)? {
+            if let Some(step) = self.snapshot_step(&entry?.path()) {
+                written.push(step);
+            }
+        }
+        written.sort();
+        self.written = written;
+        Ok(())
+    }
+    fn is_retained(&self, step: u32, rank_from_last: usize) -> bool {
+        rank_from_last < self.keep_last
+            || self
+                .keep_every
+                .is_some_and(|keep_every| (step / self.every).is_multiple_of(keep_every))
+    }
+    // Forgets and removes the checkpoints no longer retained.
+    fn apply_retention(&mut self) {
+        let n_written = self.written.len();
+        let (retained, removed) = self
+            .written
+            .iter()
+            .enumerate()
+            .partition::<Vec<_>, _>(|(i, step)| self.is_retained(**step, n_written - 1 - i));
+        for (_, step) in removed {
+            for path in [self.snapshot_path(*step), self.stats_path(*step)] {
+                if let Err(err) = std::fs::remove_file(&path) {
+                    warn!("Checkpoint '{path:?}' could not be removed: {err}");
+                }
+            }
+        }
+        self.written = retained.into_iter().map(|(_, step)| *step).collect();
+    }
+}
+
+// Saves the ecosystem when the current step is a checkpoint step.
+pub fn checkpoint_if_due(world: &mut World) {
+    let steps = world.resource::<EcosystemRuntime>().steps;
+    let Some(mut checkpointing) = world.resource_mut::<Simulation>().checkpointing.take() else {
+        return;
+    };
+    if steps.is_multiple_of(checkpointing.every) {
+        if let Err(err) = write_checkpoint(&checkpointing, steps, world) {
+            error!("Checkpoint at step {steps} failed: {err}");
+        } else {
+            // A checkpoint of the same step, from before a resume, was overwritten.
+            checkpointing.written.retain(|step| *step != steps);
+            checkpointing.written.push(steps);
+            checkpointing.apply_retention();
+        }
+    }
+    world.resource_mut::<Simulation>().checkpointing = Some(checkpointing);
+}
+
+fn write_checkpoint(
+    checkpointing: &Checkpointing,
+    steps: u32,
+    world: &mut World,
+) -> Result<(), SaveError> {
+    std::fs::create_dir_all(&checkpointing.directory)?;
+    // The snapshot is written last, so that a snapshot always has its statistics.
+    let stats = world.resource::<EcosystemStatistics>().sprint(steps);
+    write_atomically(&checkpointing.stats_path(steps), stats.as_bytes())?;
+    let data = serialize_ecosystem(world, checkpointing.format)?;
+    write_atomically(&checkpointing.snapshot_path(steps), &data)?;
+    Ok(())
+}
+
+// Writes a temporary file renamed into place,
+// so that an interrupted write never leaves a truncated checkpoint.
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temporary_path = temporary_path(path);
+    std::fs::write(&temporary_path, data)?;
+    std::fs::rename(&temporary_path, path)
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    PathBuf::from(temporary_path)
+}
+
+// Most recent checkpoint snapshot of the directory.
+pub fn latest_checkpoint(directory: &Path) -> Result<PathBuf, SaveError> {
+    let mut snapshots = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_snapshot = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.starts_with("step_") && !name.ends_with(".stats") && !name.ends_with(".tmp")
+            });
+        if is_snapshot {
+            snapshots.push(path);
+        }
+    }
+    // Steps are zero padded, so names are ordered by step.
+    snapshots.into_iter().max().ok_or_else(|| {
+        SaveError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no checkpoint in {directory:?}"),
+        ))
+    })
+}
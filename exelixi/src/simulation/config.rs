@@ -7,10 +7,31 @@ use crate::prelude::*;
 pub struct Simulation {
     pub control: SimulationControl,
     pub load: Option<PathBuf>,
+    // The load path is resumed, it can be a checkpoint directory,
+    // and the simulation does not start from another state when it cannot be loaded.
+    pub resume: bool,
     // Total active running of the simulation
     pub run: Option<u32>,
     // Default path to save
     pub save: Option<PathBuf>,
+    // Encoding of the save, when None it is deduced from the save path.
+    pub save_format: Option<SaveFormat>,
+    // Periodic saves, when enabled.
+    pub checkpointing: Option<Checkpointing>,
+    // Organism files to import, into the named species or the one they were exported from.
+    pub imports: Vec<(PathBuf, Option<String>)>,
+    // Organisms to export to files.
+    pub exports: Vec<(ExportedOrganism, PathBuf)>,
+    // Species whose organisms chromosomes are exported to gene pool files.
+    pub gene_pool_exports: Vec<(String, PathBuf)>,
+    // Config file watched for changes, when enabled.
+    pub config_watch: Option<ConfigWatch>,
     // Defines if the simulation should exit after doing all defined steps in load/run/save
     pub exit: bool,
 }
+
+// Organism to export, selected in the GUI or the champion of a species.
+pub enum ExportedOrganism {
+    Entity(Entity),
+    Champion(String),
+}
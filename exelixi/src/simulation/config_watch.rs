@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::prelude::*;
+
+// Watches the config file, to apply its changes to the running ecosystem.
+pub struct ConfigWatch {
+    pub path: PathBuf,
+    // Modification time of the file when it was last read.
+    modified: Option<SystemTime>,
+}
+impl ConfigWatch {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modification_time(&path);
+        Self { path, modified }
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Applies the config file changes, when it was modified since it was last read.
+pub fn reload_config_if_changed(world: &mut World) {
+    let Some(watch) = world.resource_mut::<Simulation>().config_watch.take() else {
+        return;
+    };
+    let modified = modification_time(&watch.path);
+    if modified != watch.modified {
+        match read_config(&watch.path).and_then(|config| apply_config_change(world, config)) {
+            Ok(changes) if changes.is_empty() => {}
+            Ok(changes) => {
+                let steps = world.resource::<EcosystemRuntime>().steps;
+                info!(
+                    "Config changes from '{:?}' applied at step {steps}:\n  {}",
+                    watch.path,
+                    changes.join("\n  ")
+                );
+            }
+            Err(err) => error!("Config changes from '{:?}' rejected: {err}", watch.path),
+        }
+    }
+    world.resource_mut::<Simulation>().config_watch = Some(ConfigWatch { modified, ..watch });
+}
+
+fn read_config(path: &Path) -> Result<EcosystemConfig, String> {
+    let ron_string = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let user_config =
+        ron::from_str::<UserEcosystemConfig>(&ron_string).map_err(|err| err.to_string())?;
+    EcosystemConfig::from_user_config(user_config)
+        .map_err(|report| format!("invalid config:\n{report}"))
+}
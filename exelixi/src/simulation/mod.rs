@@ -2,25 +2,69 @@ use std::{path::PathBuf, time::Instant};
 
 use crate::prelude::*;
 
+mod checkpoint;
 mod config;
+mod config_watch;
 mod control;
 
 use bevy::{app::AppExit, ecs::system::RunSystemOnce};
+pub use checkpoint::*;
 pub use config::*;
+pub use config_watch::*;
 pub use control::*;
 
 pub struct SimulationPlugin {
     pub load_path: Option<PathBuf>,
+    // The load path is a save or checkpoint directory to resume.
+    pub resume: bool,
     pub run_for: Option<u32>,
     pub save_path: Option<PathBuf>,
+    pub save_format: Option<SaveFormat>,
+    pub checkpoint_directory: Option<PathBuf>,
+    pub checkpoint_every: u32,
+    pub checkpoint_keep_last: usize,
+    pub checkpoint_keep_every: Option<u32>,
+    pub import_organisms: Vec<PathBuf>,
+    pub import_species: Option<String>,
+    pub export_organism: Option<(PathBuf, String)>,
+    pub export_gene_pool: Option<(PathBuf, String)>,
+    // Config file whose changes are applied while running.
+    pub watch_config: Option<PathBuf>,
     pub exit: bool,
 }
 impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Simulation {
             load: self.load_path.clone(),
+            resume: self.resume,
             run: self.run_for,
             save: self.save_path.clone(),
+            save_format: self.save_format,
+            checkpointing: self.checkpoint_directory.clone().map(|directory| {
+                Checkpointing::new(
+                    directory,
+                    self.checkpoint_every,
+                    self.save_format.unwrap_or_default(),
+                    self.checkpoint_keep_last,
+                    self.checkpoint_keep_every,
+                )
+            }),
+            imports: self
+                .import_organisms
+                .iter()
+                .map(|path| (path.clone(), self.import_species.clone()))
+                .collect(),
+            exports: self
+                .export_organism
+                .iter()
+                .map(|(path, species)| (ExportedOrganism::Champion(species.clone()), path.clone()))
+                .collect(),
+            gene_pool_exports: self
+                .export_gene_pool
+                .iter()
+                .map(|(path, species)| (species.clone(), path.clone()))
+                .collect(),
+            config_watch: self.watch_config.clone().map(ConfigWatch::new),
             exit: self.exit,
             ..Default::default()
         });
@@ -40,13 +84,51 @@ const MAX_SIMULATION_DURATION_PER_FRAME: f32 = 1.0 / (MIN_FPS as f32);
 pub fn run_ecosystem_schedule(world: &mut World) {
     // Check for load request and apply
     if let Some(path) = world.get_resource_mut::<Simulation>().unwrap().load.take() {
-        crate::ecosystem::load_ecosystem_from_file(&path, world);
+        let simulation = world.resource::<Simulation>();
+        let (resume, exit) = (simulation.resume, simulation.exit);
+        let loaded = if resume && path.is_dir() {
+            latest_checkpoint(&path)
+        } else {
+            Ok(path.clone())
+        }
+        .and_then(|path| crate::ecosystem::load_ecosystem_from_file(&path, world).map(|_| path));
+        match loaded {
+            Ok(path) if resume => {
+                let steps = world.resource::<EcosystemRuntime>().steps;
+                info!("Simulation resumed from '{path:?}' at step {steps}");
+                if let Some(checkpointing) = &mut world.resource_mut::<Simulation>().checkpointing {
+                    if let Err(err) = checkpointing.rebuild_written() {
+                        warn!(
+                            "Checkpoints of '{:?}' could not be listed: {err}",
+                            checkpointing.directory
+                        );
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                error!("Ecosystem at path '{path:?}' could not be loaded: {err}");
+                // Do not run a headless or resumed simulation from another state than the requested one.
+                if exit || resume {
+                    world.send_event(AppExit::error());
+                    return;
+                }
+            }
+        }
+    }
+    reload_config_if_changed(world);
+    // Import requested organisms, they are born at the next step
+    for (path, species) in std::mem::take(&mut world.resource_mut::<Simulation>().imports) {
+        if let Err(err) = import_organism(world, &path, species.as_deref()) {
+            error!("Organism at path '{path:?}' could not be imported: {err}");
+        }
     }
     // Run the simulation for the defined number of steps
     if let Some(n_steps) = world.get_resource_mut::<Simulation>().unwrap().run.take() {
         let start_steps = world.get_resource::<EcosystemRuntime>().unwrap().steps;
         loop {
             world.run_schedule(EcosystemSchedule);
+            checkpoint_if_due(world);
             let cur_steps = world.get_resource::<EcosystemRuntime>().unwrap().steps;
             // Always give back control on generation increase
             if cur_steps >= start_steps + n_steps {
@@ -60,6 +142,7 @@ pub fn run_ecosystem_schedule(world: &mut World) {
             let mut cur_steps = 0;
             loop {
                 world.run_schedule(EcosystemSchedule);
+                checkpoint_if_due(world);
                 cur_steps += 1;
 
                 // Give back control every 1/60s
@@ -75,9 +158,34 @@ pub fn run_ecosystem_schedule(world: &mut World) {
             }
         }
     }
+    // Export requested organisms
+    for (organism, path) in std::mem::take(&mut world.resource_mut::<Simulation>().exports) {
+        let entity = match organism {
+            ExportedOrganism::Entity(entity) => Some(entity),
+            ExportedOrganism::Champion(species) => champion_organism(world, &species),
+        };
+        let exported = entity
+            .ok_or_else(|| SaveError::Serialization("no organism to export".to_string()))
+            .and_then(|entity| export_organism(world, entity, &path));
+        if let Err(err) = exported {
+            error!("Organism could not be exported to '{path:?}': {err}");
+        }
+    }
+    for (species, path) in std::mem::take(&mut world.resource_mut::<Simulation>().gene_pool_exports)
+    {
+        if let Err(err) = export_gene_pool(world, &species, &path) {
+            error!("Gene pool could not be exported to '{path:?}': {err}");
+        }
+    }
     // Check for save request and apply
     if let Some(save_path) = world.resource_mut::<Simulation>().save.take() {
-        crate::ecosystem::save_ecosystem_to_file(&save_path, world);
+        let format = world
+            .resource::<Simulation>()
+            .save_format
+            .unwrap_or_else(|| SaveFormat::from_path(&save_path));
+        if let Err(err) = crate::ecosystem::save_ecosystem_to_file(&save_path, format, world) {
+            error!("Ecosystem could not be saved to '{save_path:?}': {err}");
+        }
     }
     // Check for exit request, store and dump last statistics and exit
     if world.resource::<Simulation>().exit {
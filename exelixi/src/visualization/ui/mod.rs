@@ -21,7 +21,11 @@ pub struct UiState {
     pub simulation_open: bool,
     pub simulation_population_checked: BTreeMap<SpeciesId, bool>,
     pub simulation_energy_checked: BTreeMap<SpeciesId, bool>,
+    // Index of the trait plotted in the simulation panel, the last one being the genetic diversity.
+    pub simulation_trait: usize,
     pub selection_open: bool,
+    // Path of the organism file to import from the selection panel.
+    pub import_path: String,
 }
 //
 pub struct UiPlugin;
@@ -6,8 +6,19 @@ use bevy_egui::egui::{Align2, CollapsingHeader};
 pub fn ui_selection(
     mut contexts: EguiContexts,
     mut ui_state: ResMut<UiState>,
-    selection: Query<(Entity, &Organism, &Position, &Body, Option<&Eye>), With<Selected>>,
+    selection: Query<
+        (
+            Entity,
+            &Organism,
+            &Position,
+            &Body,
+            Option<&Eye>,
+            Option<&Brain>,
+        ),
+        With<Selected>,
+    >,
     ecosystem_config: ResMut<EcosystemConfig>,
+    mut simulation: ResMut<Simulation>,
 ) {
     egui::containers::Window::new("Selection")
         .anchor(Align2::RIGHT_TOP, (-10.0, 10.0))
@@ -18,7 +29,7 @@ pub fn ui_selection(
                 ui_state.selection_open = !ui_state.selection_open;
             }
             if ui_state.selection_open {
-                if let Ok((entity, organism, position, body, eye)) = selection.get_single() {
+                if let Ok((entity, organism, position, body, eye, brain)) = selection.get_single() {
                     let species_color =
                         ecosystem_config.get_egui_color(&organism.species, 1.0, 0.7);
                     let species_name = ecosystem_config.get_species_name(&organism.species);
@@ -29,8 +40,28 @@ pub fn ui_selection(
                                 .small()
                                 .color(species_color),
                         );
+                        if ui.small_button("Export").clicked() {
+                            simulation.exports.push((
+                                ExportedOrganism::Entity(entity),
+                                format!("{species_name}_{}.organism", entity.index()).into(),
+                            ));
+                        }
                     });
                     ui.label(format!("Age: {}", organism.age));
+                    ui.label(format!(
+                        "Id: {} Parents: {}",
+                        organism.id.0,
+                        if organism.parents.is_empty() {
+                            "none".to_string()
+                        } else {
+                            organism
+                                .parents
+                                .iter()
+                                .map(|parent| parent.0.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        }
+                    ));
                     CollapsingHeader::new("Position")
                         .default_open(false)
                         .show(ui, |ui| {
@@ -75,9 +106,64 @@ pub fn ui_selection(
                                 );
                             });
                     }
+                    if let Some(brain) = brain {
+                        if brain.nn.is_recurrent() {
+                            CollapsingHeader::new("Brain")
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    CollapsingHeader::new("Memory:").default_open(false).show(
+                                        ui,
+                                        |ui| {
+                                            egui::Grid::new("Brain memory")
+                                                .spacing((0.0, 0.0))
+                                                .show(ui, |ui| {
+                                                    for layer_memory in brain
+                                                        .memory
+                                                        .iter()
+                                                        .filter(|m| !m.is_empty())
+                                                    {
+                                                        for value in layer_memory.iter() {
+                                                            ui.label(get_text_for_neuron(*value));
+                                                        }
+                                                        ui.end_row()
+                                                    }
+                                                })
+                                        },
+                                    );
+                                });
+                        }
+                    }
+                    let layout = &ecosystem_config.species[&organism.species].chromosome_layout;
+                    if !layout.is_empty() {
+                        CollapsingHeader::new("Genes")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                egui::ScrollArea::vertical()
+                                    .max_height(300.0)
+                                    .show(ui, |ui| {
+                                        egui::Grid::new("Genes").striped(true).show(ui, |ui| {
+                                            for (locus, gene) in
+                                                layout.iter().zip(organism.chromosome.iter())
+                                            {
+                                                ui.small(locus.name.as_str());
+                                                ui.small(format!("{gene:.3}"));
+                                                ui.end_row()
+                                            }
+                                        })
+                                    });
+                            });
+                    }
                 } else {
                     ui.label(RichText::new("<Select an organism>").color(HONEY));
                 }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut ui_state.import_path);
+                    if ui.small_button("Import").clicked() && !ui_state.import_path.is_empty() {
+                        simulation
+                            .imports
+                            .push((ui_state.import_path.clone().into(), None));
+                    }
+                });
             }
         });
 }
@@ -39,16 +39,14 @@ pub fn ui_simulation(
                                 .color(color),
                             );
                             if *checked {
-                                plot_lines.push(
-                                    egui_plot::Line::new(
-                                        stats
-                                            .accumulation
-                                            .iter()
-                                            .map(|(step, stat)| [*step as f64, stat.size as f64])
-                                            .collect::<Vec<_>>(),
-                                    )
-                                    .color(color),
-                                );
+                                plot_lines.extend(envelope_lines(
+                                    &stats.accumulation,
+                                    color,
+                                    |stat| {
+                                        let (min, max) = stat.size_range();
+                                        (min as f64, stat.size as f64, max as f64)
+                                    },
+                                ));
                             }
                         }
                         if plot_lines.is_empty() {
@@ -59,6 +57,14 @@ pub fn ui_simulation(
                                 for plot_line in plot_lines {
                                     plot_ui.line(plot_line);
                                 }
+                                // Steps at which the config was changed while running
+                                for (step, changes) in ecosystem_statistics.config_changes.iter() {
+                                    plot_ui.vline(
+                                        egui_plot::VLine::new(*step as f64)
+                                            .color(HONEY)
+                                            .name(changes.join("\n")),
+                                    );
+                                }
                             });
                         }
                     });
@@ -82,18 +88,14 @@ pub fn ui_simulation(
                                     .color(color),
                                 );
                                 if *checked {
-                                    plot_lines.push(
-                                        egui_plot::Line::new(
-                                            stats
-                                                .accumulation
-                                                .iter()
-                                                .map(|(step, stat)| {
-                                                    [*step as f64, stat.energy_total as f64]
-                                                })
-                                                .collect::<Vec<_>>(),
-                                        )
-                                        .color(color),
-                                    );
+                                    plot_lines.extend(envelope_lines(
+                                        &stats.accumulation,
+                                        color,
+                                        |stat| {
+                                            let (min, max) = stat.energy_total_range();
+                                            (min as f64, stat.energy_total as f64, max as f64)
+                                        },
+                                    ));
                                 }
                             }
                         }
@@ -108,6 +110,85 @@ pub fn ui_simulation(
                             });
                         }
                     });
+                CollapsingHeader::new("Traits")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        let names = SpeciesTraits::default()
+                            .distributions()
+                            .map(|(name, _)| name);
+                        let diversity_index = names.len();
+                        ui.horizontal_wrapped(|ui| {
+                            for (i, name) in names.iter().enumerate() {
+                                ui.selectable_value(&mut ui_state.simulation_trait, i, *name);
+                            }
+                            ui.selectable_value(
+                                &mut ui_state.simulation_trait,
+                                diversity_index,
+                                "Genetic diversity",
+                            );
+                        });
+                        let mut plot_lines = vec![];
+                        for (species, stats) in ecosystem_statistics.organisms.iter() {
+                            let color = ecosystem_config.get_egui_color(species, 1.0, 0.7);
+                            if ui_state.simulation_trait == diversity_index {
+                                let points = stats
+                                    .accumulation
+                                    .iter()
+                                    .filter_map(|(step, stat)| {
+                                        stat.traits
+                                            .diversity
+                                            .map(|diversity| [*step as f64, diversity as f64])
+                                    })
+                                    .collect::<Vec<_>>();
+                                if !points.is_empty() {
+                                    plot_lines.push(egui_plot::Line::new(points).color(color));
+                                }
+                                continue;
+                            }
+                            // Median as a solid line, within the 10th and 90th percentiles
+                            let distributions = stats
+                                .accumulation
+                                .iter()
+                                .filter_map(|(step, stat)| {
+                                    stat.traits.distributions()[ui_state.simulation_trait]
+                                        .1
+                                        .clone()
+                                        .map(|distribution| (*step as f64, distribution))
+                                })
+                                .collect::<Vec<_>>();
+                            if distributions.is_empty() {
+                                continue;
+                            }
+                            let line = |value: fn(&TraitDistribution) -> f32| {
+                                distributions
+                                    .iter()
+                                    .map(|(step, distribution)| [*step, value(distribution) as f64])
+                                    .collect::<Vec<_>>()
+                            };
+                            plot_lines.push(
+                                egui_plot::Line::new(line(|d| d.p50))
+                                    .color(color)
+                                    .name(&stats.name),
+                            );
+                            for percentile in [line(|d| d.p10), line(|d| d.p90)] {
+                                plot_lines.push(
+                                    egui_plot::Line::new(percentile)
+                                        .color(color.gamma_multiply(0.5))
+                                        .style(egui_plot::LineStyle::dashed_loose()),
+                                );
+                            }
+                        }
+                        if plot_lines.is_empty() {
+                            ui.label(RichText::new("No data yet").color(HONEY));
+                        } else {
+                            let plot = egui_plot::Plot::new("Traits").height(80.0);
+                            plot.show(ui, |plot_ui| {
+                                for plot_line in plot_lines {
+                                    plot_ui.line(plot_line);
+                                }
+                            });
+                        }
+                    });
                 CollapsingHeader::new("Generation")
                     .default_open(true)
                     .show(ui, |ui| {
@@ -128,3 +209,29 @@ pub fn ui_simulation(
             }
         });
 }
+
+// Line of the mean values, with faded lines of their lowest and highest values
+// where older statistics were merged.
+fn envelope_lines(
+    accumulation: &[(u32, SpeciesStatistic)],
+    color: egui::Color32,
+    stat_values: fn(&SpeciesStatistic) -> (f64, f64, f64),
+) -> Vec<egui_plot::Line> {
+    let values = accumulation
+        .iter()
+        .map(|(step, stat)| (*step as f64, stat_values(stat)))
+        .collect::<Vec<_>>();
+    let line = |value: fn(&(f64, f64, f64)) -> f64| {
+        values
+            .iter()
+            .map(|(step, values)| [*step, value(values)])
+            .collect::<Vec<_>>()
+    };
+    let mut lines = vec![egui_plot::Line::new(line(|(_, mean, _)| *mean)).color(color)];
+    if accumulation.iter().any(|(_, stat)| stat.envelope.is_some()) {
+        for envelope in [line(|(min, _, _)| *min), line(|(_, _, max)| *max)] {
+            lines.push(egui_plot::Line::new(envelope).color(color.gamma_multiply(0.4)));
+        }
+    }
+    lines
+}
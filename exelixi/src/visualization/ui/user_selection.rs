@@ -39,8 +39,8 @@ pub fn user_selection(
                     let position = Position::new(world_pos.x, world_pos.y, 0.0);
                     let mut nearest = kdtree
                         .per_species
-                        .iter()
-                        .filter_map(|(_, tree)| {
+                        .values()
+                        .filter_map(|tree| {
                             tree.nearest(&KdTreeEntry::new(&position, Entity::PLACEHOLDER))
                         })
                         .collect::<Vec<_>>();
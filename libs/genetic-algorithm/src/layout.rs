@@ -0,0 +1,116 @@
+use bevy_reflect::Reflect;
+
+use crate::Chromosome;
+
+/// Kind of value a gene encodes.
+#[derive(Clone, Copy, Default, Reflect, Debug, PartialEq)]
+pub enum GeneKind {
+    #[default]
+    Continuous,
+    /// Rounded to the closest integer.
+    Integer,
+    /// 0.0 for false, 1.0 for true.
+    Boolean,
+}
+
+/// Description of one gene of a chromosome.
+#[derive(Clone, Default, Reflect, Debug)]
+pub struct Locus {
+    /// Dot-separated name, the first part being the name of the segment the gene belongs to.
+    /// ex: eye.fov_angle, brain.l0.n3.w2
+    pub name: String,
+    pub kind: GeneKind,
+    /// Inclusive bounds of the gene value, they can be infinite.
+    pub min: f32,
+    pub max: f32,
+}
+impl Locus {
+    pub fn new(name: impl Into<String>, kind: GeneKind, min: f32, max: f32) -> Self {
+        assert!(min <= max);
+        Self {
+            name: name.into(),
+            kind,
+            min,
+            max,
+        }
+    }
+    pub fn continuous(name: impl Into<String>, min: f32, max: f32) -> Self {
+        Self::new(name, GeneKind::Continuous, min, max)
+    }
+    pub fn integer(name: impl Into<String>, min: f32, max: f32) -> Self {
+        Self::new(name, GeneKind::Integer, min, max)
+    }
+    pub fn boolean(name: impl Into<String>) -> Self {
+        Self::new(name, GeneKind::Boolean, 0.0, 1.0)
+    }
+    pub fn is_bounded(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+    // Closest valid value of this gene.
+    pub fn repair(&self, value: f32) -> f32 {
+        match self.kind {
+            GeneKind::Continuous => value.clamp(self.min, self.max),
+            GeneKind::Integer => value.round().clamp(self.min, self.max),
+            GeneKind::Boolean => {
+                if value >= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+    // Name of the segment this gene belongs to.
+    pub fn segment(&self) -> &str {
+        self.name.split('.').next().unwrap_or_default()
+    }
+}
+
+/// Description of all genes of the chromosomes of a population, in order.
+#[derive(Clone, Default, Reflect, Debug)]
+pub struct ChromosomeLayout {
+    loci: Vec<Locus>,
+}
+impl ChromosomeLayout {
+    pub fn new(loci: Vec<Locus>) -> Self {
+        Self { loci }
+    }
+    pub fn len(&self) -> usize {
+        self.loci.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.loci.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &Locus> {
+        self.loci.iter()
+    }
+    pub fn get(&self, index: usize) -> Option<&Locus> {
+        self.loci.get(index)
+    }
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.loci.iter().position(|locus| locus.name == name)
+    }
+    /// Value of the named gene in the chromosome.
+    pub fn value(&self, chromosome: &Chromosome, name: &str) -> Option<f32> {
+        self.index_of(name)
+            .and_then(|index| chromosome.genes.get(index).copied())
+    }
+    /// Number of consecutive genes of each segment.
+    pub fn segments(&self) -> Vec<usize> {
+        let mut segments: Vec<(&str, usize)> = vec![];
+        for locus in self.loci.iter() {
+            match segments.last_mut() {
+                Some((segment, length)) if *segment == locus.segment() => *length += 1,
+                _ => segments.push((locus.segment(), 1)),
+            }
+        }
+        segments.into_iter().map(|(_, length)| length).collect()
+    }
+    /// Brings every gene back within its bounds and kind.
+    /// Genes after the last locus are left untouched.
+    pub fn repair(&self, chromosome: &mut Chromosome) {
+        for (gene, locus) in chromosome.iter_mut().zip(self.loci.iter()) {
+            *gene = locus.repair(*gene);
+        }
+    }
+}
@@ -1,9 +1,24 @@
-#![feature(type_alias_impl_trait)]
 #![feature(impl_trait_in_assoc_type)]
+mod layout;
+mod neat;
+mod niching;
+mod objectives;
+
 use std::ops::Index;
 
 use bevy_reflect::Reflect;
-use rand::{prelude::SliceRandom, Rng, RngCore};
+use rand::{
+    distributions::{Distribution, WeightedError, WeightedIndex},
+    prelude::SliceRandom,
+    Rng, RngCore,
+};
+use rand_distr::StandardNormal;
+
+pub use layout::*;
+pub use neat::*;
+pub use niching::Niching;
+use niching::Scored;
+pub use objectives::*;
 
 pub trait Individual {
     fn fitness(&self) -> f32;
@@ -16,9 +31,18 @@ pub trait SelectionMethod {
     fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I
     where
         I: Individual;
+    // Select n individuals among the same population, an individual can be selected several times.
+    // Methods ranking the population override it to rank the population only once.
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], n: usize) -> Vec<&'a I>
+    where
+        I: Individual,
+    {
+        (0..n).map(|_| self.select(rng, population)).collect()
+    }
 }
 
 pub trait CrossoverMethod: Send + Sync {
+    // parent_a is the fitter parent, when their fitness is known.
     fn crossover(
         &self,
         rng: &mut dyn RngCore,
@@ -33,13 +57,16 @@ pub trait MutationMethod: Send + Sync {
 #[derive(Clone, Default, Reflect, Debug)]
 pub struct Chromosome {
     genes: Vec<f32>,
+    // Optional variable-size genome, for topology-evolving networks.
+    neat: Option<NeatGenome>,
 }
 impl Chromosome {
     pub fn len(&self) -> usize {
         self.genes.len()
     }
+    // True when the chromosome has neither genes nor NEAT genome.
     pub fn is_empty(&self) -> bool {
-        self.genes.is_empty()
+        self.genes.is_empty() && self.neat.is_none()
     }
     pub fn iter(&self) -> impl Iterator<Item = &f32> {
         self.genes.iter()
@@ -50,6 +77,37 @@ impl Chromosome {
     pub fn extend(&mut self, iter: impl IntoIterator<Item = f32>) {
         self.genes.extend(iter);
     }
+    // Appends the other chromosome genes, and takes its NEAT genome when it has one.
+    pub fn append(&mut self, other: Chromosome) {
+        self.genes.extend(other.genes);
+        if other.neat.is_some() {
+            self.neat = other.neat;
+        }
+    }
+    pub fn neat(&self) -> Option<&NeatGenome> {
+        self.neat.as_ref()
+    }
+    pub fn with_neat(mut self, neat: NeatGenome) -> Self {
+        self.neat = Some(neat);
+        self
+    }
+    // Mean absolute difference of the genes, plus the compatibility distance of the NEAT genomes.
+    pub fn distance(&self, other: &Chromosome) -> f32 {
+        let n_genes = self.len().min(other.len());
+        let mut distance = if n_genes == 0 {
+            0.0
+        } else {
+            self.iter()
+                .zip(other.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum::<f32>()
+                / n_genes as f32
+        };
+        if let (Some(a), Some(b)) = (&self.neat, &other.neat) {
+            distance += a.compatibility(b);
+        }
+        distance
+    }
 }
 impl Index<usize> for Chromosome {
     type Output = f32;
@@ -62,6 +120,7 @@ impl FromIterator<f32> for Chromosome {
     fn from_iter<T: IntoIterator<Item = f32>>(iter: T) -> Self {
         Self {
             genes: iter.into_iter().collect(),
+            neat: None,
         }
     }
 }
@@ -91,12 +150,116 @@ impl SelectionMethod for RouletteWheelSelection {
     where
         I: Individual,
     {
-        population
-            .choose_weighted(rng, |individual| individual.fitness())
-            .expect("Got an empty population to choose from")
+        match population.choose_weighted(rng, |individual| individual.fitness()) {
+            Ok(individual) => individual,
+            // When no individual has any fitness, like a whole starved generation,
+            // all are equally likely to be selected.
+            Err(WeightedError::AllWeightsZero) => population
+                .choose(rng)
+                .expect("Got an empty population to choose from"),
+            Err(err) => panic!("Roulette wheel selection failed: {err}"),
+        }
+    }
+}
+
+/// Selects the fittest among `size` individuals picked at random.
+#[derive(Clone, Debug)]
+pub struct TournamentSelection {
+    size: usize,
+}
+impl TournamentSelection {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+        Self { size }
+    }
+}
+impl SelectionMethod for TournamentSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I
+    where
+        I: Individual,
+    {
+        (0..self.size)
+            .map(|_| {
+                population
+                    .choose(rng)
+                    .expect("Got an empty population to choose from")
+            })
+            .max_by(|a, b| a.fitness().total_cmp(&b.fitness()))
+            .unwrap()
     }
 }
 
+/// Selects an individual with a probability proportional to its rank by fitness,
+/// the least fit having rank 1. Unlike roulette wheel selection,
+/// the selection pressure does not depend on the fitness scale.
+#[derive(Clone, Debug, Default)]
+pub struct RankSelection;
+impl RankSelection {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl SelectionMethod for RankSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I
+    where
+        I: Individual,
+    {
+        self.select_many(rng, population, 1)[0]
+    }
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], n: usize) -> Vec<&'a I>
+    where
+        I: Individual,
+    {
+        let ranked = sorted_by_fitness(population);
+        let ranks = WeightedIndex::new((1..=ranked.len()).rev())
+            .expect("Got an empty population to choose from");
+        (0..n).map(|_| ranked[ranks.sample(rng)]).collect()
+    }
+}
+
+/// Selects uniformly among the fittest `ratio` of the population.
+#[derive(Clone, Debug)]
+pub struct TruncationSelection {
+    ratio: f32,
+}
+impl TruncationSelection {
+    pub fn new(ratio: f32) -> Self {
+        assert!(ratio > 0.0 && ratio <= 1.0);
+        Self { ratio }
+    }
+}
+impl SelectionMethod for TruncationSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I
+    where
+        I: Individual,
+    {
+        self.select_many(rng, population, 1)[0]
+    }
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], n: usize) -> Vec<&'a I>
+    where
+        I: Individual,
+    {
+        let ranked = sorted_by_fitness(population);
+        let n_kept = ((ranked.len() as f32 * self.ratio).ceil() as usize).max(1);
+        let kept = &ranked[..n_kept.min(ranked.len())];
+        (0..n)
+            .map(|_| {
+                *kept
+                    .choose(rng)
+                    .expect("Got an empty population to choose from")
+            })
+            .collect()
+    }
+}
+
+// Population sorted from the fittest to the least fit.
+// The sort is stable so that equally fit individuals keep their population order.
+fn sorted_by_fitness<I: Individual>(population: &[I]) -> Vec<&I> {
+    let mut sorted = population.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| b.fitness().total_cmp(&a.fitness()));
+    sorted
+}
+
 pub struct UniformCrossover;
 impl UniformCrossover {
     pub fn new() -> Self {
@@ -123,6 +286,137 @@ impl Default for UniformCrossover {
         UniformCrossover::new()
     }
 }
+
+/// Genes before a random point come from parent_a, the others from parent_b.
+#[derive(Default)]
+pub struct SinglePointCrossover;
+impl SinglePointCrossover {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl CrossoverMethod for SinglePointCrossover {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+        let point = rng.gen_range(0..=parent_a.len());
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(i, (a, b))| if i < point { *a } else { *b })
+            .collect()
+    }
+}
+
+/// Genes between two random points come from parent_b, the others from parent_a.
+#[derive(Default)]
+pub struct TwoPointCrossover;
+impl TwoPointCrossover {
+    pub fn new() -> Self {
+        Self
+    }
+}
+impl CrossoverMethod for TwoPointCrossover {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+        let p1 = rng.gen_range(0..=parent_a.len());
+        let p2 = rng.gen_range(0..=parent_a.len());
+        let middle = p1.min(p2)..p1.max(p2);
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(i, (a, b))| if middle.contains(&i) { *b } else { *a })
+            .collect()
+    }
+}
+
+/// Blend crossover, BLX-alpha.
+/// Each child gene is drawn uniformly in the interval between the parent genes,
+/// extended on both sides by alpha times its length.
+pub struct ArithmeticBlendCrossover {
+    alpha: f32,
+}
+impl ArithmeticBlendCrossover {
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha >= 0.0);
+        Self { alpha }
+    }
+}
+impl CrossoverMethod for ArithmeticBlendCrossover {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(a, b)| {
+                if a == b {
+                    *a
+                } else {
+                    let extent = (a - b).abs() * self.alpha;
+                    rng.gen_range(a.min(*b) - extent..=a.max(*b) + extent)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Each segment of consecutive genes is inherited as a whole from one random parent.
+/// Genes after the last segment form an additional segment.
+pub struct SegmentCrossover {
+    /// Number of genes of each segment.
+    segments: Vec<usize>,
+}
+impl SegmentCrossover {
+    pub fn new(segments: Vec<usize>) -> Self {
+        Self { segments }
+    }
+}
+impl CrossoverMethod for SegmentCrossover {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+        let mut child = Vec::with_capacity(parent_a.len());
+        let mut start = 0;
+        let ends = self
+            .segments
+            .iter()
+            .map(|length| {
+                start += length;
+                start.min(parent_a.len())
+            })
+            .chain(std::iter::once(parent_a.len()))
+            .collect::<Vec<_>>();
+        for end in ends {
+            let parent = if rng.gen_bool(0.5) {
+                parent_a
+            } else {
+                parent_b
+            };
+            child.extend_from_slice(&parent.genes[child.len()..end.max(child.len())]);
+        }
+        child.into_iter().collect()
+    }
+}
 pub struct GaussianMutation {
     /// Probability of changing a gene:
     /// - 0.0 = no genes will be touched
@@ -152,10 +446,215 @@ impl MutationMethod for GaussianMutation {
     }
 }
 
+/// Adds a normally distributed value to the touched genes.
+pub struct NormalMutation {
+    /// Probability of changing a gene.
+    chance: f32,
+    /// Standard deviation of the change.
+    sigma: f32,
+}
+impl NormalMutation {
+    pub fn new(chance: f32, sigma: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(sigma >= 0.0);
+        Self { chance, sigma }
+    }
+}
+impl MutationMethod for NormalMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        for gene in chromosome.iter_mut() {
+            if rng.gen_bool(self.chance as f64) {
+                *gene += self.sigma * rng.sample::<f32, _>(StandardNormal);
+            }
+        }
+    }
+}
+
+/// Replaces the touched genes with a uniformly random value within their bounds.
+pub struct UniformResetMutation {
+    /// Probability of resetting a gene.
+    chance: f32,
+    /// Inclusive (min, max) bounds of each gene.
+    /// Genes after the last bounds are never reset.
+    bounds: Vec<(f32, f32)>,
+}
+impl UniformResetMutation {
+    pub fn new(chance: f32, bounds: Vec<(f32, f32)>) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(bounds.iter().all(|(min, max)| min <= max));
+        Self { chance, bounds }
+    }
+}
+impl MutationMethod for UniformResetMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        for (gene, &(min, max)) in chromosome.iter_mut().zip(self.bounds.iter()) {
+            if rng.gen_bool(self.chance as f64) {
+                *gene = rng.gen_range(min..=max);
+            }
+        }
+    }
+}
+
+/// Normal mutation whose standard deviation is the last gene of the chromosome.
+/// The step size is itself mutated first, log-normally,
+/// so that it evolves along with the genes it applies to.
+pub struct SelfAdaptiveMutation {
+    /// Probability of changing a gene, the step size is always changed.
+    chance: f32,
+    /// Standard deviation of the step size logarithm change.
+    learning_rate: f32,
+}
+impl SelfAdaptiveMutation {
+    /// Smallest step size, so that a vanishing step can grow back.
+    pub const MINIMUM_STEP: f32 = 1e-3;
+    pub fn new(chance: f32, learning_rate: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(learning_rate >= 0.0);
+        Self {
+            chance,
+            learning_rate,
+        }
+    }
+}
+impl MutationMethod for SelfAdaptiveMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        let Some((step, genes)) = chromosome.genes.split_last_mut() else {
+            return;
+        };
+        *step = (*step * (self.learning_rate * rng.sample::<f32, _>(StandardNormal)).exp())
+            .max(Self::MINIMUM_STEP);
+        for gene in genes.iter_mut() {
+            if rng.gen_bool(self.chance as f64) {
+                *gene += *step * rng.sample::<f32, _>(StandardNormal);
+            }
+        }
+    }
+}
+
+/// Genes of a connection that can be disabled: its weight,
+/// and whether it is enabled, a boolean gene of 1.0 when enabled and 0.0 when disabled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConnectionLoci {
+    pub weight: usize,
+    pub enabled: usize,
+}
+impl ConnectionLoci {
+    pub fn new(weight: usize, enabled: usize) -> Self {
+        Self { weight, enabled }
+    }
+    fn is_enabled(&self, chromosome: &Chromosome) -> bool {
+        chromosome.genes[self.enabled] >= 0.5
+    }
+}
+
+/// Switches boolean genes between 0.0 and 1.0,
+/// typically the enabled genes of the connections of a neural network.
+pub struct ToggleMutation {
+    /// Probability of switching a gene.
+    chance: f32,
+    /// Indices of the boolean genes that can be switched.
+    loci: Vec<usize>,
+}
+impl ToggleMutation {
+    pub fn new(chance: f32, loci: Vec<usize>) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        Self { chance, loci }
+    }
+}
+impl MutationMethod for ToggleMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        if self.chance == 0.0 {
+            return;
+        }
+        for &locus in self.loci.iter() {
+            if rng.gen_bool(self.chance as f64) {
+                let gene = &mut chromosome.genes[locus];
+                *gene = if *gene >= 0.5 { 0.0 } else { 1.0 };
+            }
+        }
+    }
+}
+/// Keeps the enabled genes of connections out of another mutation or crossover,
+/// so that only a ToggleMutation switches them:
+/// - a mutation leaves the enabled genes, and the weights of disabled connections, unchanged
+/// - a crossover copies a connection disabled in either parent from one of the parents,
+///   its weight and its enabled gene together
+pub struct KeepDisabled<M> {
+    inner: M,
+    connections: Vec<ConnectionLoci>,
+}
+impl<M> KeepDisabled<M> {
+    pub fn new(inner: M, connections: Vec<ConnectionLoci>) -> Self {
+        Self { inner, connections }
+    }
+}
+impl<M: MutationMethod> MutationMethod for KeepDisabled<M> {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        let kept = self
+            .connections
+            .iter()
+            .map(|connection| {
+                (
+                    *connection,
+                    connection.is_enabled(chromosome),
+                    chromosome.genes[connection.weight],
+                )
+            })
+            .collect::<Vec<_>>();
+        self.inner.mutate(rng, chromosome);
+        for (connection, enabled, weight) in kept {
+            chromosome.genes[connection.enabled] = if enabled { 1.0 } else { 0.0 };
+            if !enabled {
+                chromosome.genes[connection.weight] = weight;
+            }
+        }
+    }
+}
+impl<C: CrossoverMethod> CrossoverMethod for KeepDisabled<C> {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        let mut child = self.inner.crossover(rng, parent_a, parent_b);
+        for connection in self.connections.iter() {
+            if connection.is_enabled(parent_a) && connection.is_enabled(parent_b) {
+                child.genes[connection.enabled] = 1.0;
+            } else {
+                let parent = if rng.gen_bool(0.5) {
+                    parent_a
+                } else {
+                    parent_b
+                };
+                child.genes[connection.weight] = parent.genes[connection.weight];
+                child.genes[connection.enabled] = parent.genes[connection.enabled];
+            }
+        }
+        child
+    }
+}
+/// Applies both mutations, one after the other.
+impl<A, B> MutationMethod for (A, B)
+where
+    A: MutationMethod,
+    B: MutationMethod,
+{
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        self.0.mutate(rng, chromosome);
+        self.1.mutate(rng, chromosome);
+    }
+}
+
 pub struct GeneticAlgorithm<S> {
     selection_method: S,
     crossover_method: Box<dyn CrossoverMethod>,
     mutation_method: Box<dyn MutationMethod>,
+    // Number of fittest chromosomes copied unchanged into the next generation.
+    elitism: usize,
+    // When provided, children genes are brought back within their bounds.
+    layout: Option<ChromosomeLayout>,
+    niching: Option<Niching>,
 }
 
 impl<S> GeneticAlgorithm<S>
@@ -171,8 +670,26 @@ where
             selection_method,
             crossover_method: Box::new(crossover_method),
             mutation_method: Box::new(mutation_method),
+            elitism: 0,
+            layout: None,
+            niching: None,
         }
     }
+    pub fn with_elitism(mut self, elitism: usize) -> Self {
+        self.elitism = elitism;
+        self
+    }
+    pub fn with_layout(mut self, layout: ChromosomeLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+    pub fn with_niching(mut self, niching: Niching) -> Self {
+        self.niching = Some(niching);
+        self
+    }
+    pub fn niching(&self) -> Option<&Niching> {
+        self.niching.as_ref()
+    }
     pub fn evolve<I>(
         &self,
         rng: &mut dyn RngCore,
@@ -180,6 +697,23 @@ where
         fertility_rate: f32,
         minimum_population: usize,
     ) -> Vec<I>
+    where
+        I: Individual,
+    {
+        self.evolve_with_parents(rng, population, fertility_rate, minimum_population)
+            .into_iter()
+            .map(|(individual, _)| individual)
+            .collect()
+    }
+    /// Same as evolve, each child being returned with the indices in population of its parents:
+    /// a single one for elites, two for children of a crossover.
+    pub fn evolve_with_parents<I>(
+        &self,
+        rng: &mut dyn RngCore,
+        population: &[I],
+        fertility_rate: f32,
+        minimum_population: usize,
+    ) -> Vec<(I, Vec<usize>)>
     where
         I: Individual,
     {
@@ -194,21 +728,279 @@ where
                 0
             };
         let n_children = n_children.max(minimum_population);
-        let new_chromosomes = (0..n_children)
-            .map(|_| {
-                let parent_a = self.selection_method.select(rng, population);
-                let parent_b = self.selection_method.select(rng, population);
-                self.crossover_method
-                    .crossover(rng, parent_a.chromosome(), parent_b.chromosome())
-            })
+        // The fittest chromosomes are kept unchanged
+        let mut ranked = (0..population.len()).collect::<Vec<_>>();
+        ranked.sort_by(|a, b| {
+            population[*b]
+                .fitness()
+                .total_cmp(&population[*a].fitness())
+        });
+        let elites = ranked
+            .into_iter()
+            .take(self.elitism.min(n_children))
+            .map(|i| (I::create(population[i].chromosome().clone()), vec![i]))
             .collect::<Vec<_>>();
+        let n_crossovers = n_children - elites.len();
+        let groups = match &self.niching {
+            Some(niching) => niching.groups(population, n_crossovers),
+            None => vec![(
+                population.iter().enumerate().map(Scored::new).collect(),
+                n_crossovers,
+            )],
+        };
+        // Both parents of a child come from the same group
+        let mut new_chromosomes = Vec::with_capacity(n_crossovers);
+        for (parents, n_group_children) in groups.iter() {
+            // Parents are selected all at once, so that the group is ranked only once.
+            let selected = self
+                .selection_method
+                .select_many(rng, parents, 2 * n_group_children);
+            for pair in selected.chunks(2) {
+                let (parent_a, parent_b) = (pair[0], pair[1]);
+                let (parent_a, parent_b) = if parent_b.fitness() > parent_a.fitness() {
+                    (parent_b, parent_a)
+                } else {
+                    (parent_a, parent_b)
+                };
+                new_chromosomes.push((
+                    self.crossover_method.crossover(
+                        rng,
+                        parent_a.chromosome(),
+                        parent_b.chromosome(),
+                    ),
+                    vec![parent_a.index, parent_b.index],
+                ));
+            }
+        }
         // Apply mutation and create new individuals
-        new_chromosomes
+        elites
             .into_iter()
-            .map(|mut c| {
+            .chain(new_chromosomes.into_iter().map(|(mut c, parents)| {
                 self.mutation_method.mutate(rng, &mut c);
-                I::create(c)
-            })
+                if let Some(layout) = &self.layout {
+                    layout.repair(&mut c);
+                }
+                (I::create(c), parents)
+            }))
             .collect::<Vec<_>>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    struct TestIndividual {
+        fitness: f32,
+        chromosome: Chromosome,
+    }
+    impl Individual for TestIndividual {
+        fn fitness(&self) -> f32 {
+            self.fitness
+        }
+        fn chromosome(&self) -> &Chromosome {
+            &self.chromosome
+        }
+        fn create(chromosome: Chromosome) -> Self {
+            Self {
+                fitness: 0.0,
+                chromosome,
+            }
+        }
+    }
+    fn population(fitnesses: &[f32]) -> Vec<TestIndividual> {
+        fitnesses
+            .iter()
+            .map(|&fitness| TestIndividual {
+                fitness,
+                chromosome: [fitness].into_iter().collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selection() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let starved = population(&[0.0, 0.0, 0.0]);
+        RouletteWheelSelection.select(&mut rng, &starved);
+
+        let population = population(&[1.0, 4.0, 2.0, 3.0]);
+        for _ in 0..10 {
+            assert!(
+                TruncationSelection::new(0.5)
+                    .select(&mut rng, &population)
+                    .fitness
+                    >= 3.0
+            );
+            assert!(
+                TournamentSelection::new(4)
+                    .select(&mut rng, &population)
+                    .fitness
+                    > 0.0
+            );
+            RankSelection.select(&mut rng, &population);
+        }
+        assert!(TruncationSelection::new(0.5)
+            .select_many(&mut rng, &population, 10)
+            .iter()
+            .all(|individual| individual.fitness >= 3.0));
+        assert_eq!(
+            RankSelection.select_many(&mut rng, &population, 10).len(),
+            10
+        );
+        let children = GeneticAlgorithm::new(
+            RankSelection,
+            UniformCrossover,
+            GaussianMutation::new(1.0, 1.0),
+        )
+        .with_elitism(2)
+        .evolve(&mut rng, &population, 1.0, 0);
+        assert_eq!(children.len(), 4);
+        assert_eq!(children[0].chromosome[0], 4.0);
+        assert_eq!(children[1].chromosome[0], 3.0);
+    }
+
+    #[test]
+    fn crossover_and_mutation() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let parent_a = [0.0; 6].into_iter().collect::<Chromosome>();
+        let parent_b = [1.0; 6].into_iter().collect::<Chromosome>();
+        for _ in 0..10 {
+            let child = SegmentCrossover::new(vec![2, 3]).crossover(&mut rng, &parent_a, &parent_b);
+            assert_eq!(child.len(), 6);
+            assert_eq!(child[0], child[1]);
+            assert_eq!(child[2], child[3]);
+            assert_eq!(child[3], child[4]);
+
+            let child =
+                ArithmeticBlendCrossover::new(0.5).crossover(&mut rng, &parent_a, &parent_b);
+            assert!(child.iter().all(|gene| (-0.5..=1.5).contains(gene)));
+
+            let mut child = TwoPointCrossover::new().crossover(&mut rng, &parent_a, &parent_b);
+            UniformResetMutation::new(1.0, vec![(2.0, 3.0); 3]).mutate(&mut rng, &mut child);
+            assert!(child.iter().take(3).all(|gene| (2.0..=3.0).contains(gene)));
+
+            SelfAdaptiveMutation::new(1.0, 1.0).mutate(&mut rng, &mut child);
+            assert!(child[5] >= SelfAdaptiveMutation::MINIMUM_STEP);
+        }
+    }
+
+    #[test]
+    fn disabled_genes() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        // Two connections, of weights 0.5 and 0.8, the second one disabled.
+        let connections = vec![ConnectionLoci::new(0, 2), ConnectionLoci::new(1, 3)];
+        let mut chromosome = [0.5, 0.8, 1.0, 0.0].into_iter().collect::<Chromosome>();
+        KeepDisabled::new(NormalMutation::new(1.0, 1.0), connections.clone())
+            .mutate(&mut rng, &mut chromosome);
+        assert!(chromosome[0] != 0.5);
+        assert_eq!(
+            (chromosome[1], chromosome[2], chromosome[3]),
+            (0.8, 1.0, 0.0)
+        );
+
+        let parent_b = [1.0; 4].into_iter().collect::<Chromosome>();
+        for _ in 0..10 {
+            let child = KeepDisabled::new(ArithmeticBlendCrossover::new(0.5), connections.clone())
+                .crossover(&mut rng, &chromosome, &parent_b);
+            assert_eq!(child[2], 1.0);
+            assert!((child[1], child[3]) == (0.8, 0.0) || (child[1], child[3]) == (1.0, 1.0));
+        }
+
+        // Toggling the connection back on restores its weight.
+        ToggleMutation::new(1.0, vec![2, 3]).mutate(&mut rng, &mut chromosome);
+        assert_eq!(
+            (chromosome[1], chromosome[2], chromosome[3]),
+            (0.8, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn layout() {
+        let layout = ChromosomeLayout::new(vec![
+            Locus::continuous("eye.fov_angle", 0.0, 1.0),
+            Locus::integer("eye.n_cells", 1.0, 5.0),
+            Locus::boolean("brain.enabled"),
+            Locus::continuous("brain.l0.n0.bias", f32::NEG_INFINITY, f32::INFINITY),
+        ]);
+        assert_eq!(layout.segments(), vec![2, 2]);
+        let mut chromosome = [1.5, 2.6, 0.7, -3.0, 8.0]
+            .into_iter()
+            .collect::<Chromosome>();
+        layout.repair(&mut chromosome);
+        assert_eq!(
+            chromosome.iter().copied().collect::<Vec<_>>(),
+            vec![1.0, 3.0, 1.0, -3.0, 8.0]
+        );
+        assert_eq!(layout.value(&chromosome, "eye.n_cells"), Some(3.0));
+    }
+
+    #[test]
+    fn niching() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut population = population(&[1.0, 1.1, 5.0, 5.2, 5.1]);
+        population[0].fitness = 0.0;
+        let chromosomes = population
+            .iter()
+            .map(|individual| &individual.chromosome)
+            .collect::<Vec<_>>();
+        let niching = Niching::clustering(1.0);
+        assert_eq!(niching.niches(&chromosomes), vec![0, 0, 1, 1, 1]);
+        let children = GeneticAlgorithm::new(
+            RouletteWheelSelection,
+            UniformCrossover,
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_niching(niching)
+        .evolve(&mut rng, &population, 2.0, 0);
+        // Niches produce children proportionally to their mean fitness 0.55 and 5.1
+        let n_small = children.iter().filter(|c| c.chromosome[0] < 2.0).count();
+        assert_eq!((n_small, children.len()), (1, 10));
+    }
+
+    #[test]
+    fn parents() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let population = population(&[1.0, 1.1, 5.0, 5.2, 5.1]);
+        let children = GeneticAlgorithm::new(
+            RouletteWheelSelection,
+            UniformCrossover,
+            GaussianMutation::new(0.0, 0.0),
+        )
+        .with_elitism(1)
+        .with_niching(Niching::clustering(1.0))
+        .evolve_with_parents(&mut rng, &population, 2.0, 0);
+        assert_eq!(children.len(), 10);
+        assert_eq!(children[0].1, vec![3]);
+        // Both parents of a child come from the same niche, the fitter one first
+        for (_, parents) in children.iter().skip(1) {
+            assert_eq!(parents.len(), 2);
+            assert_eq!(parents[0] < 2, parents[1] < 2);
+            assert!(population[parents[0]].fitness >= population[parents[1]].fitness);
+        }
+    }
+
+    #[test]
+    fn objectives() {
+        let fitness = pareto_fitness(&[
+            vec![1.0, 0.0],
+            vec![0.0, 1.0],
+            vec![0.5, 0.5],
+            vec![0.4, 0.4],
+            vec![0.0, 0.0],
+        ]);
+        assert!(fitness[0..3].iter().all(|f| *f >= 3.0 && *f < 4.0));
+        assert!(fitness[0] > fitness[2]);
+        assert!(fitness[3] >= 2.0 && fitness[3] < 3.0);
+        assert!(fitness[4] >= 1.0 && fitness[4] < 2.0);
+
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let mut archive = NoveltyArchive::new(1, 1.0);
+        let behaviours = vec![vec![0.0], vec![1.0], vec![3.0]];
+        assert_eq!(archive.novelty(&behaviours), vec![1.0, 1.0, 2.0]);
+        archive.archive(&mut rng, &[vec![2.5]]);
+        assert_eq!(archive.novelty(&behaviours), vec![1.0, 1.0, 0.5]);
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy_reflect::Reflect;
+use rand::{prelude::SliceRandom, Rng, RngCore};
+
+use crate::{Chromosome, CrossoverMethod, MutationMethod};
+
+// Number of tries to find two nodes that can be connected, before giving up.
+const ADD_CONNECTION_TRIES: usize = 20;
+// Bit set on all hidden node ids, so that they never collide with input or output node ids.
+const HIDDEN_NODE_BIT: u64 = 1 << 63;
+
+/// A node of a NEAT genome.
+/// Input nodes have ids 0..n_inputs, output nodes n_inputs..n_inputs+n_outputs,
+/// hidden nodes are identified by the connection they were created from.
+#[derive(Clone, Default, Reflect, Debug, PartialEq)]
+pub struct NodeGene {
+    pub id: u64,
+    pub bias: f32,
+}
+
+/// A connection between two nodes of a NEAT genome.
+#[derive(Clone, Default, Reflect, Debug, PartialEq)]
+pub struct ConnectionGene {
+    /// Innovation number, identical for all connections between the same two nodes,
+    /// used to align genes during crossover.
+    pub innovation: u64,
+    pub from: u64,
+    pub to: u64,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+/// A NEAT genome, describing a feed-forward network whose topology evolves.
+///
+/// Innovation numbers and hidden node ids are derived from the nodes they connect or split,
+/// so identical structural mutations in different lineages share the same innovation,
+/// without the need of a global innovation history.
+#[derive(Clone, Default, Reflect, Debug, PartialEq)]
+pub struct NeatGenome {
+    pub n_inputs: usize,
+    pub n_outputs: usize,
+    /// Output and hidden nodes, input nodes have no gene.
+    pub nodes: Vec<NodeGene>,
+    /// Connections, sorted by innovation number.
+    pub connections: Vec<ConnectionGene>,
+}
+
+impl NeatGenome {
+    pub fn new(
+        n_inputs: usize,
+        n_outputs: usize,
+        nodes: Vec<NodeGene>,
+        connections: Vec<ConnectionGene>,
+    ) -> Self {
+        let mut genome = Self {
+            n_inputs,
+            n_outputs,
+            nodes,
+            connections,
+        };
+        genome.sort();
+        genome
+    }
+    /// Genome with all inputs connected to all outputs, with random weights and biases.
+    pub fn minimal(rng: &mut dyn RngCore, n_inputs: usize, n_outputs: usize) -> Self {
+        let output_ids = (n_inputs..n_inputs + n_outputs).map(|id| id as u64);
+        let nodes = output_ids
+            .clone()
+            .map(|id| NodeGene {
+                id,
+                bias: rng.gen_range(-1.0..1.0),
+            })
+            .collect();
+        let mut connections = vec![];
+        for to in output_ids {
+            for from in 0..n_inputs as u64 {
+                connections.push(ConnectionGene {
+                    innovation: Self::innovation(from, to),
+                    from,
+                    to,
+                    weight: rng.gen_range(-1.0..1.0),
+                    enabled: true,
+                });
+            }
+        }
+        let mut genome = Self {
+            n_inputs,
+            n_outputs,
+            nodes,
+            connections,
+        };
+        genome.sort();
+        genome
+    }
+    /// Innovation number of a connection between the two nodes.
+    pub fn innovation(from: u64, to: u64) -> u64 {
+        splitmix64(splitmix64(from) ^ to)
+    }
+    /// Id of the hidden node created by splitting the connection with the given innovation.
+    pub fn split_node_id(innovation: u64) -> u64 {
+        splitmix64(innovation) | HIDDEN_NODE_BIT
+    }
+    pub fn is_input(&self, id: u64) -> bool {
+        id < self.n_inputs as u64
+    }
+    pub fn has_node(&self, id: u64) -> bool {
+        self.is_input(id) || self.nodes.iter().any(|node| node.id == id)
+    }
+    pub fn n_active_connections(&self) -> usize {
+        self.connections.iter().filter(|c| c.enabled).count()
+    }
+    /// NEAT compatibility distance: the proportion of connections that are not shared,
+    /// plus the mean weight difference of the shared connections.
+    pub fn compatibility(&self, other: &Self) -> f32 {
+        let weights = self
+            .connections
+            .iter()
+            .map(|c| (c.innovation, c.weight))
+            .collect::<BTreeMap<_, _>>();
+        let weight_differences = other
+            .connections
+            .iter()
+            .filter_map(|c| weights.get(&c.innovation).map(|w| (w - c.weight).abs()))
+            .collect::<Vec<_>>();
+        let n_max = self.connections.len().max(other.connections.len());
+        if n_max == 0 {
+            return 0.0;
+        }
+        let n_unshared =
+            self.connections.len() + other.connections.len() - 2 * weight_differences.len();
+        let mean_weight_difference = if weight_differences.is_empty() {
+            0.0
+        } else {
+            weight_differences.iter().sum::<f32>() / weight_differences.len() as f32
+        };
+        n_unshared as f32 / n_max as f32 + mean_weight_difference
+    }
+    fn sort(&mut self) {
+        self.nodes.sort_by_key(|node| node.id);
+        self.connections
+            .sort_by_key(|connection| connection.innovation);
+    }
+    // Returns true if 'to' can be reached from 'from' following enabled or disabled connections.
+    fn is_reachable(&self, from: u64, to: u64) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut to_visit = vec![from];
+        while let Some(id) = to_visit.pop() {
+            if id == to {
+                return true;
+            }
+            if visited.insert(id) {
+                to_visit.extend(
+                    self.connections
+                        .iter()
+                        .filter(|c| c.from == id)
+                        .map(|c| c.to),
+                );
+            }
+        }
+        false
+    }
+    /// Adds a connection between two random nodes that are not yet connected,
+    /// without creating a cycle.
+    pub fn add_connection(&mut self, rng: &mut dyn RngCore) {
+        let sources = (0..self.n_inputs as u64)
+            .chain(
+                self.nodes
+                    .iter()
+                    .map(|node| node.id)
+                    .filter(|id| *id & HIDDEN_NODE_BIT != 0),
+            )
+            .collect::<Vec<_>>();
+        let targets = self.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+        for _ in 0..ADD_CONNECTION_TRIES {
+            let (Some(&from), Some(&to)) = (sources.choose(rng), targets.choose(rng)) else {
+                return;
+            };
+            let innovation = Self::innovation(from, to);
+            if from == to
+                || self.connections.iter().any(|c| c.innovation == innovation)
+                || self.is_reachable(to, from)
+            {
+                continue;
+            }
+            self.connections.push(ConnectionGene {
+                innovation,
+                from,
+                to,
+                weight: rng.gen_range(-1.0..1.0),
+                enabled: true,
+            });
+            self.sort();
+            return;
+        }
+    }
+    /// Splits a random enabled connection in two, with a new hidden node in between.
+    /// The incoming connection has a weight of 1.0, the outgoing one keeps the split connection weight.
+    pub fn add_node(&mut self, rng: &mut dyn RngCore) {
+        let candidates = self
+            .connections
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.enabled && !self.has_node(Self::split_node_id(c.innovation)))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let Some(&index) = candidates.choose(rng) else {
+            return;
+        };
+        let split = &mut self.connections[index];
+        split.enabled = false;
+        let (from, to, weight) = (split.from, split.to, split.weight);
+        let id = Self::split_node_id(split.innovation);
+        self.nodes.push(NodeGene { id, bias: 0.0 });
+        self.connections.push(ConnectionGene {
+            innovation: Self::innovation(from, id),
+            from,
+            to: id,
+            weight: 1.0,
+            enabled: true,
+        });
+        self.connections.push(ConnectionGene {
+            innovation: Self::innovation(id, to),
+            from: id,
+            to,
+            weight,
+            enabled: true,
+        });
+        self.sort();
+    }
+    /// Child genome, with connections aligned by innovation number.
+    /// Matching connections are randomly inherited from one of the parents,
+    /// disjoint and excess connections are inherited from parent_a, which must be the fitter parent.
+    pub fn crossover(rng: &mut dyn RngCore, parent_a: &Self, parent_b: &Self) -> Self {
+        assert_eq!(
+            (parent_a.n_inputs, parent_a.n_outputs),
+            (parent_b.n_inputs, parent_b.n_outputs)
+        );
+        let connections_b = parent_b
+            .connections
+            .iter()
+            .map(|c| (c.innovation, c))
+            .collect::<BTreeMap<_, _>>();
+        let connections = parent_a
+            .connections
+            .iter()
+            .map(|a| match connections_b.get(&a.innovation) {
+                Some(&b) if rng.gen_bool(0.5) => b.clone(),
+                _ => a.clone(),
+            })
+            .collect::<Vec<_>>();
+        let nodes_b = parent_b
+            .nodes
+            .iter()
+            .map(|n| (n.id, n))
+            .collect::<BTreeMap<_, _>>();
+        let nodes = parent_a
+            .nodes
+            .iter()
+            .map(|a| match nodes_b.get(&a.id) {
+                Some(&b) if rng.gen_bool(0.5) => b.clone(),
+                _ => a.clone(),
+            })
+            .collect::<Vec<_>>();
+        let mut child = Self {
+            n_inputs: parent_a.n_inputs,
+            n_outputs: parent_a.n_outputs,
+            nodes,
+            connections,
+        };
+        child.sort();
+        child
+    }
+}
+
+/// Crossover of chromosomes that carry a NEAT genome.
+/// The fixed-length genes use the inner crossover method, the NEAT genomes are aligned by innovation.
+pub struct NeatCrossover<C> {
+    inner: C,
+}
+impl<C: CrossoverMethod> NeatCrossover<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+}
+impl<C: CrossoverMethod> CrossoverMethod for NeatCrossover<C> {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome {
+        let mut child = self.inner.crossover(rng, parent_a, parent_b);
+        child.neat = match (&parent_a.neat, &parent_b.neat) {
+            (Some(a), Some(b)) => Some(NeatGenome::crossover(rng, a, b)),
+            (Some(a), None) => Some(a.clone()),
+            (None, Some(b)) => Some(b.clone()),
+            (None, None) => None,
+        };
+        child
+    }
+}
+
+/// Mutation of the NEAT genome of a chromosome, chromosomes without NEAT genome are untouched.
+pub struct NeatMutation {
+    /// Probability of changing each weight and bias.
+    chance: f32,
+    /// Magnitude of the weight and bias change.
+    coeff: f32,
+    /// Probability of splitting a connection with a new node.
+    add_node_chance: f32,
+    /// Probability of adding a new connection.
+    add_connection_chance: f32,
+}
+impl NeatMutation {
+    pub fn new(chance: f32, coeff: f32, add_node_chance: f32, add_connection_chance: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(coeff >= 0.0);
+        assert!((0.0..=1.0).contains(&add_node_chance));
+        assert!((0.0..=1.0).contains(&add_connection_chance));
+        Self {
+            chance,
+            coeff,
+            add_node_chance,
+            add_connection_chance,
+        }
+    }
+}
+impl MutationMethod for NeatMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, chromosome: &mut Chromosome) {
+        let Some(genome) = chromosome.neat.as_mut() else {
+            return;
+        };
+        let values = genome
+            .nodes
+            .iter_mut()
+            .map(|node| &mut node.bias)
+            .chain(genome.connections.iter_mut().map(|c| &mut c.weight));
+        for value in values {
+            if rng.gen_bool(self.chance as f64) {
+                let sign = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+                *value += sign * self.coeff * rng.gen::<f32>();
+            }
+        }
+        if rng.gen_bool(self.add_node_chance as f64) {
+            genome.add_node(rng);
+        }
+        if rng.gen_bool(self.add_connection_chance as f64) {
+            genome.add_connection(rng);
+        }
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn crossover_aligns_innovations() {
+        let mut rng = ChaCha8Rng::from_seed(Default::default());
+        let parent_a = NeatGenome::minimal(&mut rng, 2, 1);
+        assert!(!Chromosome::default().with_neat(parent_a.clone()).is_empty());
+        let mut parent_b = parent_a.clone();
+        parent_b.add_node(&mut rng);
+        assert_eq!(parent_b.nodes.len(), 2);
+        assert_eq!(parent_b.connections.len(), 4);
+        assert_eq!(parent_b.n_active_connections(), 3);
+
+        // The child follows the fitter parent_a topology, whatever parent_b structure.
+        let child = NeatGenome::crossover(&mut rng, &parent_a, &parent_b);
+        assert_eq!(child.nodes.len(), 1);
+        assert_eq!(
+            child
+                .connections
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>(),
+            parent_a
+                .connections
+                .iter()
+                .map(|c| c.innovation)
+                .collect::<Vec<_>>()
+        );
+        let child = NeatGenome::crossover(&mut rng, &parent_b, &parent_a);
+        assert_eq!(child.connections.len(), 4);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{Chromosome, Individual};
+
+/// Splits the population in niches of similar chromosomes,
+/// so that several strategies can coexist instead of the whole population converging on one.
+#[derive(Clone, Debug)]
+pub enum Niching {
+    /// The fitness of each individual is divided by its niche count,
+    /// the sum over the population of 1 - (distance / radius)^alpha for individuals closer than radius.
+    FitnessSharing { radius: f32, alpha: f32 },
+    /// Individuals are clustered into sub-species of chromosomes closer than threshold.
+    /// Each sub-species produces a number of children proportional to its mean fitness,
+    /// from parents selected among its members.
+    Clustering { threshold: f32 },
+}
+impl Niching {
+    pub fn fitness_sharing(radius: f32, alpha: f32) -> Self {
+        assert!(radius > 0.0);
+        assert!(alpha > 0.0);
+        Self::FitnessSharing { radius, alpha }
+    }
+    pub fn clustering(threshold: f32) -> Self {
+        assert!(threshold > 0.0);
+        Self::Clustering { threshold }
+    }
+    fn threshold(&self) -> f32 {
+        match *self {
+            Niching::FitnessSharing { radius, .. } => radius,
+            Niching::Clustering { threshold } => threshold,
+        }
+    }
+    /// Niche id of each chromosome, ids are consecutive starting at 0.
+    /// A chromosome joins the first niche whose first member is closer than the threshold,
+    /// the sharing radius for fitness sharing, or starts a new niche.
+    pub fn niches(&self, chromosomes: &[&Chromosome]) -> Vec<usize> {
+        let threshold = self.threshold();
+        let mut representatives: Vec<&Chromosome> = vec![];
+        chromosomes
+            .iter()
+            .map(|chromosome| {
+                match representatives
+                    .iter()
+                    .position(|representative| chromosome.distance(representative) < threshold)
+                {
+                    Some(niche) => niche,
+                    None => {
+                        representatives.push(chromosome);
+                        representatives.len() - 1
+                    }
+                }
+            })
+            .collect()
+    }
+    // Groups of parents, with the number of children each group produces.
+    pub(crate) fn groups<'a, I>(
+        &self,
+        population: &'a [I],
+        n_children: usize,
+    ) -> Vec<(Vec<Scored<'a>>, usize)>
+    where
+        I: Individual,
+    {
+        let chromosomes = population
+            .iter()
+            .map(|individual| individual.chromosome())
+            .collect::<Vec<_>>();
+        match *self {
+            Niching::FitnessSharing { radius, alpha } => {
+                let parents = population
+                    .iter()
+                    .enumerate()
+                    .map(|(index, individual)| {
+                        let niche_count = chromosomes
+                            .iter()
+                            .map(|other| individual.chromosome().distance(other))
+                            .filter(|distance| *distance < radius)
+                            .map(|distance| 1.0 - (distance / radius).powf(alpha))
+                            .sum::<f32>();
+                        Scored {
+                            index,
+                            chromosome: individual.chromosome(),
+                            fitness: individual.fitness() / niche_count,
+                        }
+                    })
+                    .collect();
+                vec![(parents, n_children)]
+            }
+            Niching::Clustering { .. } => {
+                let niches = self.niches(&chromosomes);
+                let n_niches = niches.iter().max().map_or(0, |max| max + 1);
+                let mut groups = vec![vec![]; n_niches];
+                for (individual, niche) in population.iter().enumerate().zip(niches) {
+                    groups[niche].push(Scored::new(individual));
+                }
+                let mean_fitnesses = groups
+                    .iter()
+                    .map(|group| {
+                        group.iter().map(|scored| scored.fitness).sum::<f32>() / group.len() as f32
+                    })
+                    .collect::<Vec<_>>();
+                let quotas = if mean_fitnesses.iter().sum::<f32>() > 0.0 {
+                    quotas(&mean_fitnesses, n_children)
+                } else {
+                    let sizes = groups.iter().map(|g| g.len() as f32).collect::<Vec<_>>();
+                    quotas(&sizes, n_children)
+                };
+                groups.into_iter().zip(quotas).collect()
+            }
+        }
+    }
+}
+
+// Splits n proportionally to the weights, the remainder going to the largest fractional parts.
+fn quotas(weights: &[f32], n: usize) -> Vec<usize> {
+    let total = weights.iter().sum::<f32>();
+    let exact = weights
+        .iter()
+        .map(|weight| weight / total * n as f32)
+        .collect::<Vec<_>>();
+    let mut quotas = exact.iter().map(|q| q.floor() as usize).collect::<Vec<_>>();
+    let mut by_remainder = (0..weights.len()).collect::<Vec<_>>();
+    by_remainder.sort_by(|a, b| (exact[*b] % 1.0).total_cmp(&(exact[*a] % 1.0)));
+    let remaining = n.saturating_sub(quotas.iter().sum());
+    for i in by_remainder.into_iter().cycle().take(remaining) {
+        quotas[i] += 1;
+    }
+    quotas
+}
+
+// A chromosome with the fitness used for its selection.
+#[derive(Clone)]
+pub(crate) struct Scored<'a> {
+    // Index of the individual in the population.
+    pub(crate) index: usize,
+    chromosome: &'a Chromosome,
+    fitness: f32,
+}
+impl<'a> Scored<'a> {
+    pub(crate) fn new<I: Individual>((index, individual): (usize, &'a I)) -> Self {
+        Self {
+            index,
+            chromosome: individual.chromosome(),
+            fitness: individual.fitness(),
+        }
+    }
+}
+impl Individual for Scored<'_> {
+    fn fitness(&self) -> f32 {
+        self.fitness
+    }
+    fn chromosome(&self) -> &Chromosome {
+        self.chromosome
+    }
+    fn create(_chromosome: Chromosome) -> Self {
+        unreachable!("Scored chromosomes are only used for selection")
+    }
+}
@@ -0,0 +1,156 @@
+use bevy_reflect::Reflect;
+use rand::{Rng, RngCore};
+
+/// Scalar fitness of individuals evaluated on several objectives, all to be maximized,
+/// following the NSGA-II crowded comparison.
+/// Individuals of a better Pareto front always have a higher fitness,
+/// and within a front, the less crowded individuals have a higher fitness.
+/// All fitnesses are at least 1.0.
+pub fn pareto_fitness(objectives: &[Vec<f32>]) -> Vec<f32> {
+    let fronts = pareto_fronts(objectives);
+    let mut fitness = vec![0.0; objectives.len()];
+    for (rank, front) in fronts.iter().enumerate() {
+        let crowding = crowding_distances(objectives, front);
+        for (&i, distance) in front.iter().zip(crowding) {
+            // Crowding is mapped to [0.0, 0.5] so that it never reaches the next front.
+            let crowding = if distance.is_infinite() {
+                0.5
+            } else {
+                0.5 * distance / (1.0 + distance)
+            };
+            fitness[i] = (fronts.len() - rank) as f32 + crowding;
+        }
+    }
+    fitness
+}
+
+// a dominates b when it is at least as good on all objectives, and better on one.
+fn dominates(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a >= b) && a.iter().zip(b).any(|(a, b)| a > b)
+}
+
+// Indices of the individuals of each front, starting with the non-dominated front.
+fn pareto_fronts(objectives: &[Vec<f32>]) -> Vec<Vec<usize>> {
+    let n = objectives.len();
+    let mut dominated_by = vec![0; n];
+    let mut dominating = vec![vec![]; n];
+    for a in 0..n {
+        for b in 0..n {
+            if dominates(&objectives[a], &objectives[b]) {
+                dominating[a].push(b);
+                dominated_by[b] += 1;
+            }
+        }
+    }
+    let mut fronts = vec![];
+    let mut front = (0..n).filter(|i| dominated_by[*i] == 0).collect::<Vec<_>>();
+    while !front.is_empty() {
+        let mut next_front = vec![];
+        for &a in front.iter() {
+            for &b in dominating[a].iter() {
+                dominated_by[b] -= 1;
+                if dominated_by[b] == 0 {
+                    next_front.push(b);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next_front;
+    }
+    fronts
+}
+
+// Crowding distance of each individual of the front, infinite for the extremes of an objective.
+fn crowding_distances(objectives: &[Vec<f32>], front: &[usize]) -> Vec<f32> {
+    let mut distances = vec![0.0; front.len()];
+    let n_objectives = objectives[front[0]].len();
+    let columns = (0..n_objectives).map(|objective| {
+        front
+            .iter()
+            .map(|i| objectives[*i][objective])
+            .collect::<Vec<_>>()
+    });
+    for values in columns {
+        let mut order = (0..front.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        let range = values[last] - values[first];
+        distances[first] = f32::INFINITY;
+        distances[last] = f32::INFINITY;
+        if range > 0.0 {
+            for window in order.windows(3) {
+                distances[window[1]] += (values[window[2]] - values[window[0]]) / range;
+            }
+        }
+    }
+    distances
+}
+
+/// Archive of past behaviours for novelty search,
+/// where individuals are rewarded for behaving differently from the others.
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct NoveltyArchive {
+    /// Number of nearest neighbours the novelty is measured against.
+    k_nearest: usize,
+    /// Probability of each evaluated behaviour to be added to the archive.
+    archive_chance: f32,
+    behaviours: Vec<Vec<f32>>,
+}
+impl NoveltyArchive {
+    pub fn new(k_nearest: usize, archive_chance: f32) -> Self {
+        assert!(k_nearest > 0);
+        assert!((0.0..=1.0).contains(&archive_chance));
+        Self {
+            k_nearest,
+            archive_chance,
+            behaviours: vec![],
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.behaviours.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.behaviours.is_empty()
+    }
+    /// Novelty of each behaviour, the mean euclidean distance to its k nearest neighbours
+    /// among the other behaviours and the archive.
+    pub fn novelty(&self, behaviours: &[Vec<f32>]) -> Vec<f32> {
+        behaviours
+            .iter()
+            .enumerate()
+            .map(|(i, behaviour)| {
+                let mut distances = behaviours
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, other)| other)
+                    .chain(self.behaviours.iter())
+                    .map(|other| distance(behaviour, other))
+                    .collect::<Vec<_>>();
+                distances.sort_by(|a, b| a.total_cmp(b));
+                distances.truncate(self.k_nearest);
+                if distances.is_empty() {
+                    0.0
+                } else {
+                    distances.iter().sum::<f32>() / distances.len() as f32
+                }
+            })
+            .collect()
+    }
+    /// Adds each behaviour to the archive with the archive chance.
+    pub fn archive(&mut self, rng: &mut dyn RngCore, behaviours: &[Vec<f32>]) {
+        for behaviour in behaviours {
+            if rng.gen_bool(self.archive_chance as f64) {
+                self.behaviours.push(behaviour.clone());
+            }
+        }
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
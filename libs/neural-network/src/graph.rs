@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use bevy_reflect::Reflect;
+
+use crate::Activation;
+
+/// A connection between two nodes of a GraphNetwork.
+#[derive(Debug, Reflect, Default, Clone, PartialEq)]
+pub struct GraphConnection {
+    pub from: u64,
+    pub to: u64,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+// A node input, as the index of the source value during propagation.
+#[derive(Debug, Reflect, Default, Clone)]
+struct GraphInput {
+    slot: usize,
+    weight: f32,
+}
+
+#[derive(Debug, Reflect, Default, Clone)]
+struct GraphNode {
+    id: u64,
+    bias: f32,
+    inputs: Vec<GraphInput>,
+}
+
+/// A feed-forward network with an arbitrary acyclic topology.
+/// Input nodes have ids 0..n_inputs, output nodes n_inputs..n_inputs+n_outputs,
+/// hidden nodes can have any other id.
+#[derive(Debug, Reflect, Default)]
+pub struct GraphNetwork {
+    n_inputs: usize,
+    // Output and hidden nodes, in evaluation order.
+    nodes: Vec<GraphNode>,
+    // Index in the propagation values of each output node.
+    output_slots: Vec<usize>,
+    connections: Vec<GraphConnection>,
+    hidden_activation: Activation,
+    output_activation: Activation,
+}
+
+impl GraphNetwork {
+    /// Builds the network from its non-input nodes, given as (id, bias), and its connections.
+    /// Panics if the enabled connections create a cycle.
+    pub fn new(
+        n_inputs: usize,
+        n_outputs: usize,
+        nodes: &[(u64, f32)],
+        connections: Vec<GraphConnection>,
+        hidden_activation: Activation,
+        output_activation: Activation,
+    ) -> Self {
+        let biases = nodes.iter().cloned().collect::<BTreeMap<_, _>>();
+        // Topological sort of the nodes, following enabled connections.
+        let mut slots = (0..n_inputs as u64)
+            .map(|id| (id, id as usize))
+            .collect::<BTreeMap<_, _>>();
+        let mut ordered_nodes = vec![];
+        while ordered_nodes.len() < biases.len() {
+            let ready = biases
+                .keys()
+                .filter(|id| !slots.contains_key(id))
+                .filter(|id| {
+                    connections
+                        .iter()
+                        .filter(|c| c.enabled && c.to == **id)
+                        .all(|c| slots.contains_key(&c.from))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            assert!(!ready.is_empty(), "GraphNetwork contains a cycle");
+            for id in ready {
+                let inputs = connections
+                    .iter()
+                    .filter(|c| c.enabled && c.to == id)
+                    .map(|c| GraphInput {
+                        slot: slots[&c.from],
+                        weight: c.weight,
+                    })
+                    .collect();
+                slots.insert(id, n_inputs + ordered_nodes.len());
+                ordered_nodes.push(GraphNode {
+                    id,
+                    bias: biases[&id],
+                    inputs,
+                });
+            }
+        }
+        let output_slots = (n_inputs..n_inputs + n_outputs)
+            .map(|id| slots[&(id as u64)])
+            .collect();
+        Self {
+            n_inputs,
+            nodes: ordered_nodes,
+            output_slots,
+            connections,
+            hidden_activation,
+            output_activation,
+        }
+    }
+    pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.n_inputs);
+        let mut values = inputs.to_vec();
+        for node in self.nodes.iter() {
+            let sum = node.bias
+                + node
+                    .inputs
+                    .iter()
+                    .map(|input| values[input.slot] * input.weight)
+                    .sum::<f32>();
+            let activation = if self.is_output(node.id) {
+                self.output_activation
+            } else {
+                self.hidden_activation
+            };
+            values.push(activation.apply(sum));
+        }
+        self.output_slots.iter().map(|slot| values[*slot]).collect()
+    }
+    fn is_output(&self, id: u64) -> bool {
+        (self.n_inputs as u64..(self.n_inputs + self.output_slots.len()) as u64).contains(&id)
+    }
+    pub fn n_inputs(&self) -> usize {
+        self.n_inputs
+    }
+    pub fn n_outputs(&self) -> usize {
+        self.output_slots.len()
+    }
+    /// Non-input nodes as (id, bias), in evaluation order.
+    pub fn nodes(&self) -> impl Iterator<Item = (u64, f32)> + '_ {
+        self.nodes.iter().map(|node| (node.id, node.bias))
+    }
+    pub fn connections(&self) -> &[GraphConnection] {
+        &self.connections
+    }
+    pub fn n_active_connections(&self) -> usize {
+        self.connections.iter().filter(|c| c.enabled).count()
+    }
+}
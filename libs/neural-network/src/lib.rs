@@ -1,9 +1,63 @@
+mod graph;
+
 use std::iter::once;
 
 use bevy_reflect::Reflect;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub use graph::*;
+
 pub struct LayerTopology {
     pub neurons: usize,
+    // Activation applied to the outputs of the neurons of this layer.
+    // Unused for the input layer.
+    pub activation: Activation,
+    // When true, the layer neurons receive, as extra inputs, the layer outputs of the previous propagation.
+    // Unused for the input layer.
+    pub recurrent: bool,
+}
+
+// Activation function applied on the weighted sum of a neuron's inputs.
+#[derive(Debug, Reflect, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Activation {
+    // max(0,x)
+    #[default]
+    Relu,
+    // 1/(1+e^-x), in range 0..1
+    Sigmoid,
+    // tanh(x), in range -1..1
+    Tanh,
+    // x when positive, slope*x otherwise.
+    LeakyRelu(f32),
+    // x
+    Identity,
+    // 1 when positive, 0 otherwise.
+    Step,
+}
+impl Activation {
+    pub fn apply(&self, x: f32) -> f32 {
+        match *self {
+            Activation::Relu => x.max(0.0),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::LeakyRelu(slope) => {
+                if x > 0.0 {
+                    x
+                } else {
+                    slope * x
+                }
+            }
+            Activation::Identity => x,
+            Activation::Step => {
+                if x > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Reflect, Default)]
@@ -16,74 +70,203 @@ impl Network {
         assert!(layers.len() > 1);
         let built_layers = layers
             .windows(2)
-            .map(|layers| Layer::random(rng, layers[0].neurons, layers[1].neurons))
+            .map(|layers| {
+                Layer::random(
+                    rng,
+                    layers[0].neurons,
+                    layers[1].neurons,
+                    layers[1].activation,
+                    layers[1].recurrent,
+                )
+            })
             .collect();
         Self {
             layers: built_layers,
         }
     }
+    // Propagation without memory, recurrent layers see their previous outputs as 0.0.
     pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        self.propagate_with_memory(inputs, &mut vec![])
+    }
+    // Propagation where each recurrent layer receives its outputs stored in memory,
+    // memory is then updated with the new outputs.
+    // An empty memory is initialized to 0.0 values.
+    pub fn propagate_with_memory(&self, inputs: &[f32], memory: &mut Vec<Vec<f32>>) -> Vec<f32> {
+        if memory.is_empty() {
+            *memory = self.empty_memory();
+        }
+        assert_eq!(memory.len(), self.layers.len());
+        self.layers.iter().zip(memory.iter_mut()).fold(
+            inputs.to_vec(),
+            |inputs, (layer, layer_memory)| {
+                if layer.recurrent {
+                    let outputs = layer.propagate([inputs, layer_memory.clone()].concat());
+                    layer_memory.clone_from(&outputs);
+                    outputs
+                } else {
+                    layer.propagate(inputs)
+                }
+            },
+        )
+    }
+    // Memory with 0.0 values for each neuron of recurrent layers.
+    pub fn empty_memory(&self) -> Vec<Vec<f32>> {
         self.layers
             .iter()
-            .fold(inputs.to_vec(), |inputs, layer| layer.propagate(inputs))
+            .map(|layer| {
+                if layer.recurrent {
+                    vec![0.0; layer.neurons.len()]
+                } else {
+                    vec![]
+                }
+            })
+            .collect()
+    }
+    pub fn is_recurrent(&self) -> bool {
+        self.layers.iter().any(|layer| layer.recurrent)
     }
+    // Disabled connections are returned as a 0.0 weight.
     pub fn weights(&self) -> impl Iterator<Item = f32> + '_ {
         self.layers
             .iter()
             .flat_map(|layer| layer.neurons.iter())
-            .flat_map(|neuron| once(&neuron.bias).chain(&neuron.weights))
-            .cloned()
+            .flat_map(|neuron| once(neuron.bias).chain(neuron.connection_weights()))
+    }
+    // Genes of each neuron: its bias, the weight of each input connection,
+    // then whether each input connection is enabled, as 1.0, or disabled, as 0.0.
+    // Disabled connections keep their weight, so that it is restored when they are enabled again.
+    pub fn genes(&self) -> impl Iterator<Item = f32> + '_ {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.neurons.iter())
+            .flat_map(|neuron| neuron.genes())
+    }
+    // Number of enabled connections between neurons.
+    pub fn n_active_connections(&self) -> usize {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.neurons.iter())
+            .map(|neuron| neuron.enabled.iter().filter(|e| **e).count())
+            .sum()
+    }
+    // Indices, in the genes() order, of the weight and enabled genes of each connection
+    // for the given topology.
+    pub fn connection_loci(layers: &[LayerTopology]) -> Vec<(usize, usize)> {
+        let mut loci = vec![];
+        let mut index = 0;
+        for layers in layers.windows(2) {
+            let n_inputs =
+                Layer::n_inputs(layers[0].neurons, layers[1].neurons, layers[1].recurrent);
+            for _ in 0..layers[1].neurons {
+                // Skip the bias
+                index += 1;
+                loci.extend((index..index + n_inputs).map(|weight| (weight, weight + n_inputs)));
+                index += 2 * n_inputs;
+            }
+        }
+        loci
+    }
+    // Position of each gene, in the genes() order, for the given topology, as (layer, neuron, gene).
+    pub fn gene_loci(layers: &[LayerTopology]) -> Vec<(usize, usize, NeuronGene)> {
+        let mut loci = vec![];
+        for (l, layers) in layers.windows(2).enumerate() {
+            let n_inputs =
+                Layer::n_inputs(layers[0].neurons, layers[1].neurons, layers[1].recurrent);
+            for n in 0..layers[1].neurons {
+                loci.push((l, n, NeuronGene::Bias));
+                loci.extend((0..n_inputs).map(|i| (l, n, NeuronGene::Weight(i))));
+                loci.extend((0..n_inputs).map(|i| (l, n, NeuronGene::Enabled(i))));
+            }
+        }
+        loci
     }
     #[must_use]
-    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Self {
+    pub fn from_genes(layers: &[LayerTopology], genes: impl IntoIterator<Item = f32>) -> Self {
         assert!(layers.len() > 1);
 
-        let mut weights = weights.into_iter();
+        let mut genes = genes.into_iter();
 
         let layers = layers
             .windows(2)
-            .map(|layers| Layer::from_weights(layers[0].neurons, layers[1].neurons, &mut weights))
+            .map(|layers| {
+                Layer::from_genes(
+                    layers[0].neurons,
+                    layers[1].neurons,
+                    layers[1].activation,
+                    layers[1].recurrent,
+                    &mut genes,
+                )
+            })
             .collect();
 
-        if weights.next().is_some() {
-            panic!("got too many weights");
+        if genes.next().is_some() {
+            panic!("got too many genes");
         }
 
         Self { layers }
     }
 }
 
+// Kind of a gene of a neuron, with the index of the input connection it applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeuronGene {
+    Bias,
+    Weight(usize),
+    Enabled(usize),
+}
+
 #[derive(Debug, Reflect, Default)]
 pub struct Layer {
     neurons: Vec<Neuron>,
+    activation: Activation,
+    recurrent: bool,
 }
 impl Layer {
     pub fn random(
         rng: &mut dyn rand::RngCore,
         input_neurons: usize,
         output_neurons: usize,
+        activation: Activation,
+        recurrent: bool,
     ) -> Self {
+        let n_inputs = Self::n_inputs(input_neurons, output_neurons, recurrent);
         Self {
             neurons: (0..output_neurons)
-                .map(|_| Neuron::random(rng, input_neurons))
+                .map(|_| Neuron::random(rng, n_inputs))
                 .collect(),
+            activation,
+            recurrent,
         }
     }
+    // Number of inputs of each neuron, recurrent layers have their own outputs as extra inputs.
+    fn n_inputs(input_neurons: usize, output_neurons: usize, recurrent: bool) -> usize {
+        input_neurons + if recurrent { output_neurons } else { 0 }
+    }
     pub fn propagate(&self, inputs: Vec<f32>) -> Vec<f32> {
         self.neurons
             .iter()
-            .map(|neuron| neuron.propagate(&inputs))
+            .map(|neuron| self.activation.apply(neuron.propagate(&inputs)))
             .collect()
     }
-    pub fn from_weights(
+    pub fn from_genes(
         input_size: usize,
         output_size: usize,
-        weights: &mut dyn Iterator<Item = f32>,
+        activation: Activation,
+        recurrent: bool,
+        genes: &mut dyn Iterator<Item = f32>,
     ) -> Self {
+        let n_inputs = Self::n_inputs(input_size, output_size, recurrent);
         let neurons = (0..output_size)
-            .map(|_| Neuron::from_weights(input_size, weights))
+            .map(|_| Neuron::from_genes(n_inputs, genes))
             .collect();
-        Self { neurons }
+        Self {
+            neurons,
+            activation,
+            recurrent,
+        }
+    }
+    pub fn activation(&self) -> Activation {
+        self.activation
     }
 }
 
@@ -91,29 +274,57 @@ impl Layer {
 pub struct Neuron {
     bias: f32,
     weights: Vec<f32>,
+    // Per input connection, false when the connection is disabled.
+    // A disabled connection does not contribute to the neuron output.
+    enabled: Vec<bool>,
 }
 impl Neuron {
     pub fn random(rng: &mut dyn rand::RngCore, n_inputs: usize) -> Self {
         Self {
             bias: rng.gen_range(-1.0..1.0),
             weights: (0..n_inputs).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+            enabled: vec![true; n_inputs],
         }
     }
+    // Returns the weighted sum of the inputs plus bias, before the layer activation.
     pub fn propagate(&self, inputs: &[f32]) -> f32 {
         assert_eq!(inputs.len(), self.weights.len());
         let output = inputs
             .iter()
             .zip(&self.weights)
-            .map(|(i, w)| i * w)
+            .zip(&self.enabled)
+            .filter(|(_, enabled)| **enabled)
+            .map(|((i, w), _)| i * w)
             .sum::<f32>();
-        (output + self.bias).max(0.0)
+        output + self.bias
     }
-    pub fn from_weights(output_size: usize, weights: &mut dyn Iterator<Item = f32>) -> Self {
-        let bias = weights.next().expect("Not enough weights");
-        let weights = (0..output_size)
-            .map(|_| weights.next().expect("Not enough weights"))
-            .collect();
-        Self { bias, weights }
+    // Genes in the order of genes(), an enabled gene of 0.5 or more defines an enabled connection.
+    pub fn from_genes(n_inputs: usize, genes: &mut dyn Iterator<Item = f32>) -> Self {
+        let mut next = || genes.next().expect("Not enough genes");
+        let bias = next();
+        let weights = (0..n_inputs).map(|_| next()).collect();
+        let enabled = (0..n_inputs).map(|_| next() >= 0.5).collect();
+        Self {
+            bias,
+            weights,
+            enabled,
+        }
+    }
+    fn genes(&self) -> impl Iterator<Item = f32> + '_ {
+        once(self.bias).chain(self.weights.iter().copied()).chain(
+            self.enabled
+                .iter()
+                .map(|enabled| if *enabled { 1.0 } else { 0.0 }),
+        )
+    }
+    pub fn set_enabled(&mut self, input: usize, enabled: bool) {
+        self.enabled[input] = enabled;
+    }
+    fn connection_weights(&self) -> impl Iterator<Item = f32> + '_ {
+        self.weights
+            .iter()
+            .zip(&self.enabled)
+            .map(|(w, enabled)| if *enabled { *w } else { 0.0 })
     }
 }
 
@@ -136,4 +347,104 @@ mod tests {
             &[0.67383933, 0.81812596, 0.26284885, 0.5238805]
         );
     }
+
+    #[test]
+    fn activation() {
+        let layer = Layer {
+            neurons: vec![Neuron {
+                bias: -1.0,
+                weights: vec![0.5],
+                enabled: vec![true],
+            }],
+            activation: Activation::Identity,
+            recurrent: false,
+        };
+        assert_eq!(layer.propagate(vec![1.0]), vec![-0.5]);
+        assert_eq!(Activation::Relu.apply(-0.5), 0.0);
+        assert_eq!(Activation::LeakyRelu(0.1).apply(-0.5), -0.05);
+        assert_eq!(Activation::Step.apply(0.2), 1.0);
+        assert_eq!(Activation::Sigmoid.apply(0.0), 0.5);
+        assert_eq!(Activation::Tanh.apply(0.0), 0.0);
+    }
+
+    #[test]
+    fn disabled_connections() {
+        let topology = [
+            LayerTopology {
+                neurons: 2,
+                activation: Activation::Identity,
+                recurrent: false,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Identity,
+                recurrent: false,
+            },
+        ];
+        // bias, 2 input weights, 2 enabled flags
+        let genes = [0.5, 3.0, 2.0, 0.0, 1.0];
+        let network = Network::from_genes(&topology, genes);
+        assert_eq!(network.n_active_connections(), 1);
+        assert_eq!(network.propagate(&[10.0, 1.0]), vec![2.5]);
+        assert_eq!(Network::connection_loci(&topology), vec![(1, 3), (2, 4)]);
+        assert_eq!(network.weights().collect::<Vec<_>>(), vec![0.5, 0.0, 2.0]);
+        // The disabled connection keeps its weight.
+        assert_eq!(network.genes().collect::<Vec<_>>(), genes);
+    }
+
+    #[test]
+    fn recurrent() {
+        let topology = [
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Identity,
+                recurrent: false,
+            },
+            LayerTopology {
+                neurons: 1,
+                activation: Activation::Identity,
+                recurrent: true,
+            },
+        ];
+        // bias, input weight, memory weight, and their enabled flags
+        let network = Network::from_genes(&topology, [0.0, 1.0, 0.5, 1.0, 1.0]);
+        assert_eq!(Network::connection_loci(&topology), vec![(1, 3), (2, 4)]);
+        let mut memory = vec![];
+        assert_eq!(
+            network.propagate_with_memory(&[2.0], &mut memory),
+            vec![2.0]
+        );
+        assert_eq!(
+            network.propagate_with_memory(&[0.0], &mut memory),
+            vec![1.0]
+        );
+        assert_eq!(memory, vec![vec![1.0]]);
+        assert_eq!(network.propagate(&[0.0]), vec![0.0]);
+    }
+
+    #[test]
+    fn graph() {
+        // 2 inputs, 1 output, and one hidden node between input 0 and the output.
+        let connection = |from, to, weight, enabled| GraphConnection {
+            from,
+            to,
+            weight,
+            enabled,
+        };
+        let network = GraphNetwork::new(
+            2,
+            1,
+            &[(2, 0.5), (100, 0.0)],
+            vec![
+                connection(0, 2, 1.0, false),
+                connection(0, 100, 2.0, true),
+                connection(100, 2, 1.0, true),
+                connection(1, 2, -1.0, true),
+            ],
+            Activation::Identity,
+            Activation::Identity,
+        );
+        assert_eq!(network.n_active_connections(), 3);
+        assert_eq!(network.propagate(&[1.0, 3.0]), vec![-0.5]);
+    }
 }
@@ -13,12 +13,12 @@ do
     echo "-- Running with ${CONFIG}"
     echo "---------------"
     BASENAME=$(basename $CONFIG .ron)
-    cargo run ${PROFILE} -- --seed=${SEED} --config ${CONFIG} --run-for=${STEPS_A} --save=saves/reproductibility/${BASENAME}_A.sim --exit > saves/reproductibility/${BASENAME}_A.txt
-    cargo run ${PROFILE} --  --config ${CONFIG} --load=saves/reproductibility/${BASENAME}_A.sim --run-for=${STEPS_B} --save=saves/reproductibility/${BASENAME}_B.sim --exit > saves/reproductibility/${BASENAME}_B.txt
-    cargo run ${PROFILE} -- --seed=${SEED} --config ${CONFIG} --run-for=${STEPS_C} --save=saves/reproductibility/${BASENAME}_C.sim --exit > saves/reproductibility/${BASENAME}_C.txt
+    cargo run ${PROFILE} -- --seed=${SEED} --config ${CONFIG} --run-for=${STEPS_A} --save=saves/reproductibility/${BASENAME}_A.sim --stats-output=saves/reproductibility/${BASENAME}_A.csv --exit > saves/reproductibility/${BASENAME}_A.txt
+    cargo run ${PROFILE} -- --resume=saves/reproductibility/${BASENAME}_A.sim --run-for=${STEPS_B} --save=saves/reproductibility/${BASENAME}_B.sim --stats-output=saves/reproductibility/${BASENAME}_B.csv --exit > saves/reproductibility/${BASENAME}_B.txt
+    cargo run ${PROFILE} -- --seed=${SEED} --config ${CONFIG} --run-for=${STEPS_C} --save=saves/reproductibility/${BASENAME}_C.sim --stats-output=saves/reproductibility/${BASENAME}_C.csv --exit > saves/reproductibility/${BASENAME}_C.txt
 
-    OUT_B=$(tail -n 10 saves/reproductibility/${BASENAME}_B.txt)
-    OUT_C=$(tail -n 10 saves/reproductibility/${BASENAME}_C.txt) 
+    OUT_B=$(tail -n 10 saves/reproductibility/${BASENAME}_B.csv)
+    OUT_C=$(tail -n 10 saves/reproductibility/${BASENAME}_C.csv)
     if [[ ${OUT_C} != ${OUT_B} ]]
     then
         echo "  ##### Differences for ${CONFIG} #####"